@@ -0,0 +1,214 @@
+// 无窗口运行场景文件: 直接驱动 PhysicsWorld, 每帧前进一个物理步长, 把刚体轨迹写成 csv
+//
+// 用法见 USAGE; 场景名对应 assets/scenes 下的文件, 用 cargo run 运行或设置 BEVY_ASSET_ROOT
+// 窗口程序里用代码搭的场景没有场景文件, 可以先按 F5 存成快照再用 --file 运行
+use rust::comp::physics_world::PhysicsWorld;
+use rust::comp::rigidbody::Simulate;
+use rust::scene::scene_file::{check_solver, scene_path, SceneError, SceneFile};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const USAGE: &str = "\
+用法: simulate [--scene NAME | --file SCENE.ron] [--frames N] [--out FILE] [--joints FILE]
+                [--timestep S] [--substeps N] [--iterations N]
+  --scene       assets/scenes 下的场景名, 默认 base
+  --file        任意路径的场景文件, 优先于 --scene
+  --frames      模拟的帧数, 默认 600
+  --out         刚体轨迹的 csv, 默认 trajectory.csv
+  --joints      另外把关节传感器的读数写成 csv
+  --timestep, --substeps, --iterations  覆盖场景里的求解参数";
+
+struct Args {
+    scene: String,
+    file: Option<PathBuf>,
+    frames: usize,
+    out: PathBuf,
+    joints: Option<PathBuf>,
+    timestep: Option<f32>,
+    substeps: Option<usize>,
+    iterations: Option<usize>,
+}
+
+// main 返回 Err 时按 Debug 打印, 所以 Debug 直接输出给人看的消息
+enum Error {
+    Usage(String),
+    Scene(PathBuf, SceneError),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{msg}\n{USAGE}"),
+            Error::Scene(path, err) => write!(f, "无法载入 {}: {err}", path.display()),
+            Error::Io(path, err) => write!(f, "无法写入 {}: {err}", path.display()),
+        }
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::Usage(format!("{flag} 的值 {value} 无效")))
+}
+
+// 带 --help 时打印用法并返回 None; 求解参数按场景文件的规则检查
+fn parse_args(argv: impl IntoIterator<Item = String>) -> Result<Option<Args>, Error> {
+    let mut args = Args {
+        scene: "base".to_string(),
+        file: None,
+        frames: 600,
+        out: PathBuf::from("trajectory.csv"),
+        joints: None,
+        timestep: None,
+        substeps: None,
+        iterations: None,
+    };
+    let mut iter = argv.into_iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{USAGE}");
+            return Ok(None);
+        }
+        let Some(value) = iter.next() else {
+            return Err(Error::Usage(format!("{flag} 缺少参数值")));
+        };
+        match flag.as_str() {
+            "--scene" => args.scene = value,
+            "--file" => args.file = Some(PathBuf::from(value)),
+            "--frames" => args.frames = parse(&flag, &value)?,
+            "--out" => args.out = PathBuf::from(value),
+            "--joints" => args.joints = Some(PathBuf::from(value)),
+            "--timestep" => args.timestep = Some(parse(&flag, &value)?),
+            "--substeps" => args.substeps = Some(parse(&flag, &value)?),
+            "--iterations" => args.iterations = Some(parse(&flag, &value)?),
+            _ => return Err(Error::Usage(format!("未知参数 {flag}"))),
+        }
+    }
+    check_solver(args.timestep, args.substeps, args.iterations).map_err(|err| Error::Usage(err.to_string()))?;
+    Ok(Some(args))
+}
+
+fn create(path: &PathBuf) -> Result<BufWriter<File>, Error> {
+    File::create(path).map(BufWriter::new).map_err(|err| Error::Io(path.clone(), err))
+}
+
+fn write_bodies(writer: &mut impl Write, physics: &PhysicsWorld, frame: usize) -> io::Result<()> {
+    let time = physics.time();
+    for (handle, body) in physics.bodies() {
+        let p = body.get_position();
+        let q = body.get_quat();
+        let v = body.get_velocity();
+        let w = body.get_angule_vel();
+        writeln!(writer, "{frame},{time:.6},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                 handle.index(), p.x, p.y, p.z, q.x, q.y, q.z, q.w, v.x, v.y, v.z, w.x, w.y, w.z)?;
+    }
+    Ok(())
+}
+
+fn write_joints(writer: &mut impl Write, physics: &PhysicsWorld, frame: usize) -> io::Result<()> {
+    let time = physics.time();
+    for (handle, joint) in physics.joints() {
+        let sensor = joint.get_sensor();
+        writeln!(writer, "{frame},{time:.6},{},{},{},{}",
+                 handle.index(), sensor.length, sensor.tension, sensor.axial_velocity)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Error> {
+    let Some(args) = parse_args(std::env::args().skip(1))? else {
+        return Ok(());
+    };
+
+    let path = args.file.clone().unwrap_or_else(|| scene_path(&args.scene));
+    let mut scene = SceneFile::load(&path).map_err(|err| Error::Scene(path.clone(), err))?;
+
+    // 命令行的求解参数覆盖场景里的, 再和场景一起检查和应用
+    scene.timestep = args.timestep.or(scene.timestep);
+    scene.substeps = args.substeps.or(scene.substeps);
+    scene.iterations = args.iterations.or(scene.iterations);
+    let mut physics = PhysicsWorld::new();
+    physics.ground = scene.ground;
+    scene.apply_config(&mut physics.config);
+    scene.build(&mut physics).map_err(|err| Error::Scene(path.clone(), err))?;
+
+    // 先建好输出文件, 打不开时不必模拟
+    let mut bodies_out = create(&args.out)?;
+    let mut joints_out = args.joints.as_ref().map(create).transpose()?;
+    let bodies_err = |err| Error::Io(args.out.clone(), err);
+    let joints_err = |err| Error::Io(args.joints.clone().unwrap_or_default(), err);
+
+    writeln!(bodies_out, "frame,time,body,x,y,z,qx,qy,qz,qw,vx,vy,vz,wx,wy,wz").map_err(bodies_err)?;
+    if let Some(ref mut joints_out) = joints_out {
+        writeln!(joints_out, "frame,time,joint,length,tension,axial_velocity").map_err(joints_err)?;
+    }
+    for frame in 0..=args.frames {
+        if frame > 0 {
+            physics.step();
+        }
+        write_bodies(&mut bodies_out, &physics, frame).map_err(bodies_err)?;
+        if let Some(ref mut joints_out) = joints_out {
+            write_joints(joints_out, &physics, frame).map_err(joints_err)?;
+        }
+    }
+    bodies_out.flush().map_err(bodies_err)?;
+    if let Some(ref mut joints_out) = joints_out {
+        joints_out.flush().map_err(joints_err)?;
+    }
+    println!("{} {} frames -> {}", path.display(), args.frames, args.out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Result<Option<Args>, Error> {
+        parse_args(argv.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_and_overrides() {
+        let args = parse(&[]).unwrap().unwrap();
+        assert_eq!(args.scene, "base");
+        assert_eq!(args.frames, 600);
+        assert!(args.timestep.is_none() && args.substeps.is_none() && args.iterations.is_none());
+
+        let args = parse(&["--scene", "chain", "--frames", "5", "--timestep", "0.01", "--substeps", "4",
+                           "--iterations", "2", "--joints", "joints.csv"]).unwrap().unwrap();
+        assert_eq!(args.scene, "chain");
+        assert_eq!(args.frames, 5);
+        assert_eq!(args.timestep, Some(0.01));
+        assert_eq!(args.substeps, Some(4));
+        assert_eq!(args.iterations, Some(2));
+        assert_eq!(args.joints, Some(PathBuf::from("joints.csv")));
+    }
+
+    #[test]
+    fn help_returns_none() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_solver_settings() {
+        for argv in [
+            &["--timestep", "0"][..],
+            &["--timestep", "-0.01"],
+            &["--timestep", "NaN"],
+            &["--timestep", "inf"],
+            &["--substeps", "0"],
+            &["--iterations", "0"],
+        ] {
+            assert!(matches!(parse(argv), Err(Error::Usage(_))), "{argv:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_arguments() {
+        for argv in [&["--frames"][..], &["--frames", "-1"], &["--substeps", "two"], &["--bogus", "1"]] {
+            assert!(matches!(parse(argv), Err(Error::Usage(_))), "{argv:?}");
+        }
+    }
+}
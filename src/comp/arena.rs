@@ -0,0 +1,187 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+// 带代数的句柄: 槽位被回收再利用后, 旧句柄的代数对不上, 不会取到新的元素
+pub struct Handle<T> {
+    slot: u32,
+    generation: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(slot: u32, generation: u32) -> Self {
+        Self { slot, generation, marker: PhantomData }
+    }
+
+    // 槽位序号, 同一时刻存活的句柄各不相同
+    pub fn index(&self) -> u32 {
+        self.slot
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot == other.slot && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.slot.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.slot, self.generation)
+    }
+}
+
+enum Entry<T> {
+    Occupied { generation: u32, value: T },
+    Free { generation: u32 },
+}
+
+// 用句柄访问的元素集合, 删除后的槽位放进空闲表重复使用
+pub struct Arena<T> {
+    entries: Vec<Entry<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        self.len += 1;
+        match self.free.pop() {
+            Some(slot) => {
+                let entry = &mut self.entries[slot as usize];
+                let generation = match entry {
+                    Entry::Free { generation } => *generation,
+                    Entry::Occupied { .. } => unreachable!("空闲表里的槽位被占用"),
+                };
+                *entry = Entry::Occupied { generation, value };
+                Handle::new(slot, generation)
+            }
+            None => {
+                let slot = self.entries.len() as u32;
+                self.entries.push(Entry::Occupied { generation: 0, value });
+                Handle::new(slot, 0)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        let entry = std::mem::replace(
+            &mut self.entries[handle.slot as usize],
+            Entry::Free { generation: handle.generation + 1 },
+        );
+        self.free.push(handle.slot);
+        self.len -= 1;
+        match entry {
+            Entry::Occupied { value, .. } => Some(value),
+            Entry::Free { .. } => None,
+        }
+    }
+
+    // 清空后槽位保留, 代数加一, 清空前的句柄全部失效
+    pub fn clear(&mut self) {
+        self.free.clear();
+        for (slot, entry) in self.entries.iter_mut().enumerate() {
+            let generation = match entry {
+                Entry::Occupied { generation, .. } => *generation + 1,
+                Entry::Free { generation } => *generation,
+            };
+            *entry = Entry::Free { generation };
+            self.free.push(slot as u32);
+        }
+        self.len = 0;
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        match self.entries.get(handle.slot as usize) {
+            Some(Entry::Occupied { generation, value }) if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        match self.entries.get_mut(handle.slot as usize) {
+            Some(Entry::Occupied { generation, value }) if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    // 同时取出两个不同元素的可变引用
+    pub fn get2_mut(&mut self, handle0: Handle<T>, handle1: Handle<T>) -> Option<(&mut T, &mut T)> {
+        if handle0.slot == handle1.slot || !self.contains(handle0) || !self.contains(handle1) {
+            return None;
+        }
+        let (i0, i1) = (handle0.slot as usize, handle1.slot as usize);
+        let (entry0, entry1) = if i0 < i1 {
+            let (left, right) = self.entries.split_at_mut(i1);
+            (&mut left[i0], &mut right[0])
+        } else {
+            let (left, right) = self.entries.split_at_mut(i0);
+            (&mut right[0], &mut left[i1])
+        };
+        match (entry0, entry1) {
+            (Entry::Occupied { value: value0, .. }, Entry::Occupied { value: value1, .. }) => Some((value0, value1)),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.entries.iter().enumerate().filter_map(|(slot, entry)| match entry {
+            Entry::Occupied { generation, value } => Some((Handle::new(slot as u32, *generation), value)),
+            Entry::Free { .. } => None,
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        self.entries.iter_mut().enumerate().filter_map(|(slot, entry)| match entry {
+            Entry::Occupied { generation, value } => Some((Handle::new(slot as u32, *generation), value)),
+            Entry::Free { .. } => None,
+        })
+    }
+}
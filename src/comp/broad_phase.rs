@@ -0,0 +1,110 @@
+use crate::comp::arena::Arena;
+use crate::comp::rigidbody::{BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_body(body: &RigidBody, margin: f32) -> Self {
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        // 球和胶囊的顶点是核心点, 需要再膨胀一个半径
+        for part in body.get_parts().iter() {
+            for v in part.vertices.iter() {
+                let world_pos = body.local_to_world(*v);
+                min = min.min(world_pos - Vec3::splat(part.radius));
+                max = max.max(world_pos + Vec3::splat(part.radius));
+            }
+        }
+        Self {
+            min: min - Vec3::splat(margin),
+            max: max + Vec3::splat(margin),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+            self.min.y <= other.max.y && self.max.y >= other.min.y &&
+            self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+}
+
+struct Proxy {
+    handle: BodyHandle,
+    aabb: Aabb,
+    b_static: bool,
+}
+
+// 沿 x 轴的 sweep and prune, 帧间保留排序结果, 用插入排序利用时间相关性
+pub struct BroadPhase {
+    proxies: Vec<Proxy>,
+    pairs: Vec<(BodyHandle, BodyHandle)>,
+    pub margin: f32,
+}
+
+impl Default for BroadPhase {
+    fn default() -> Self {
+        BroadPhase {
+            proxies: Vec::new(),
+            pairs: Vec::new(),
+            margin: 0.01,
+        }
+    }
+}
+
+impl BroadPhase {
+    pub fn update(&mut self, bodies: &Arena<RigidBody>) {
+        self.proxies.retain(|proxy| bodies.contains(proxy.handle));
+
+        let mut known: HashSet<BodyHandle> = HashSet::with_capacity(self.proxies.len());
+        for proxy in self.proxies.iter_mut() {
+            if let Some(body) = bodies.get(proxy.handle) {
+                proxy.aabb = Aabb::from_body(body, self.margin);
+                proxy.b_static = body.is_static();
+            }
+            known.insert(proxy.handle);
+        }
+        for (handle, body) in bodies.iter() {
+            if !known.contains(&handle) {
+                self.proxies.push(Proxy {
+                    handle,
+                    aabb: Aabb::from_body(body, self.margin),
+                    b_static: body.is_static(),
+                });
+            }
+        }
+
+        for i in 1..self.proxies.len() {
+            let mut j = i;
+            while j > 0 && self.proxies[j - 1].aabb.min.x > self.proxies[j].aabb.min.x {
+                self.proxies.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+
+        self.pairs.clear();
+        for i in 0..self.proxies.len() {
+            let proxy_0 = &self.proxies[i];
+            for proxy_1 in self.proxies[(i + 1)..].iter() {
+                if proxy_1.aabb.min.x > proxy_0.aabb.max.x {
+                    break;
+                }
+                if proxy_0.b_static && proxy_1.b_static {
+                    continue;
+                }
+                if proxy_0.aabb.overlaps(&proxy_1.aabb) {
+                    self.pairs.push((proxy_0.handle, proxy_1.handle));
+                }
+            }
+        }
+    }
+
+    pub fn pairs(&self) -> &Vec<(BodyHandle, BodyHandle)> {
+        &self.pairs
+    }
+}
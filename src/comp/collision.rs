@@ -0,0 +1,450 @@
+use crate::comp::rigidbody::{RigidBody, Simulate};
+use crate::comp::shape::ConvexPart;
+use glam::Vec3;
+
+// 世界空间下的凸多面体, 面的顶点按环绕顺序排列, 满足 dot(normal, x) <= offset 的点在面内侧
+// 面和棱直接借用 ConvexPart 预先算好的, 只变换顶点和平面
+pub struct ConvexPolyhedron<'a> {
+    pub vertices: Vec<Vec3>,
+    pub faces: &'a [Vec<usize>],
+    pub planes: Vec<(Vec3, f32)>,
+    pub edges: &'a [(usize, usize)],
+}
+
+impl<'a> ConvexPolyhedron<'a> {
+    pub fn from_part(body: &RigidBody, part: &'a ConvexPart) -> Self {
+        let origin = body.local_to_world(Vec3::ZERO);
+        let vertices = part.vertices.iter()
+            .map(|v| body.local_to_world(*v))
+            .collect();
+        let planes = part.planes.iter()
+            .map(|(normal, offset)| {
+                let normal = body.get_quat() * *normal;
+                (normal, offset + normal.dot(origin))
+            })
+            .collect();
+        Self { vertices, faces: &part.faces, planes, edges: &part.edges }
+    }
+
+    fn project(&self, axis: Vec3) -> (f32, f32) {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for v in self.vertices.iter() {
+            let d = v.dot(axis);
+            min = min.min(d);
+            max = max.max(d);
+        }
+        (min, max)
+    }
+
+    // 沿 normal 方向最靠前、且与 dir 平行的棱
+    fn support_edge(&self, dir: Vec3, normal: Vec3) -> (Vec3, Vec3) {
+        let mut best = (self.vertices[0], self.vertices[0]);
+        let mut best_d = f32::MIN;
+        for (i0, i1) in self.edges.iter() {
+            let v0 = self.vertices[*i0];
+            let v1 = self.vertices[*i1];
+            let d = v1 - v0;
+            if d.cross(dir).length_squared() > 1.0e-6 * d.length_squared() * dir.length_squared() {
+                continue;
+            }
+            let proj = (v0 + v1).dot(normal) * 0.5;
+            if proj > best_d {
+                best_d = proj;
+                best = (v0, v1);
+            }
+        }
+        best
+    }
+
+    // 法线与 normal 最反向的面
+    fn incident_face(&self, normal: Vec3) -> usize {
+        let mut best = 0;
+        let mut best_d = f32::MAX;
+        for (i, (n, _)) in self.planes.iter().enumerate() {
+            let d = n.dot(normal);
+            if d < best_d {
+                best_d = d;
+                best = i;
+            }
+        }
+        best
+    }
+}
+
+// 碰撞点对, normal 从 a 指向 b, pos_a / pos_b 为世界坐标
+pub struct ContactPoint {
+    pub normal: Vec3,
+    pub pos_a: Vec3,
+    pub pos_b: Vec3,
+}
+
+enum Feature {
+    FaceA,
+    FaceB,
+    Edges(usize, usize),
+}
+
+// 分离轴测试, 返回最小穿透的轴(由 a 指向 b)、深度和产生该轴的特征
+fn separating_axis(a: &ConvexPolyhedron, b: &ConvexPolyhedron) -> Option<(Vec3, f32, Feature)> {
+    let mut best: Option<(Vec3, f32, Feature)> = None;
+
+    let mut test_axis = |axis: Vec3, feature: Feature, bias: f32| -> bool {
+        let (min_a, max_a) = a.project(axis);
+        let (min_b, max_b) = b.project(axis);
+        let d0 = max_a - min_b;
+        let d1 = max_b - min_a;
+        if d0 <= 0.0 || d1 <= 0.0 {
+            return false;
+        }
+        let (normal, depth) = if d0 < d1 { (axis, d0) } else { (-axis, d1) };
+        match best {
+            Some((_, best_depth, _)) if depth * bias >= best_depth => {}
+            _ => best = Some((normal, depth, feature)),
+        }
+        true
+    };
+
+    for (normal, _) in a.planes.iter() {
+        if !test_axis(*normal, Feature::FaceA, 1.0) {
+            return None;
+        }
+    }
+    for (normal, _) in b.planes.iter() {
+        if !test_axis(*normal, Feature::FaceB, 1.0) {
+            return None;
+        }
+    }
+
+    for (ia, ea) in a.edges.iter().enumerate() {
+        let dir_a = a.vertices[ea.1] - a.vertices[ea.0];
+        for (ib, eb) in b.edges.iter().enumerate() {
+            let dir_b = b.vertices[eb.1] - b.vertices[eb.0];
+            let axis = dir_a.cross(dir_b);
+            if axis.length_squared() < 1.0e-4 * dir_a.length_squared() * dir_b.length_squared() {
+                continue;
+            }
+            // 棱-棱轴稍作惩罚, 优先使用面法线, 避免接触法线来回跳变
+            if !test_axis(axis.normalize(), Feature::Edges(ia, ib), 1.1) {
+                return None;
+            }
+        }
+    }
+    best
+}
+
+fn closest_points_on_segments(p0: Vec3, p1: Vec3, q0: Vec3, q1: Vec3) -> (Vec3, Vec3) {
+    let d0 = p1 - p0;
+    let d1 = q1 - q0;
+    let r = p0 - q0;
+    let a = d0.dot(d0);
+    let e = d1.dot(d1);
+    let f = d1.dot(r);
+    let c = d0.dot(r);
+    let b = d0.dot(d1);
+    let denom = a * e - b * b;
+
+    // 退化为点的线段(球的核心)
+    if a <= 1.0e-8 && e <= 1.0e-8 {
+        return (p0, q0);
+    }
+    if a <= 1.0e-8 {
+        return (p0, q0 + d1 * (f / e).clamp(0.0, 1.0));
+    }
+    if e <= 1.0e-8 {
+        return (p0 + d0 * (-c / a).clamp(0.0, 1.0), q0);
+    }
+
+    let mut s = if denom > 1.0e-8 { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+    let mut t = (b * s + f) / e;
+    if t < 0.0 {
+        t = 0.0;
+        s = (-c / a).clamp(0.0, 1.0);
+    } else if t > 1.0 {
+        t = 1.0;
+        s = ((b - c) / a).clamp(0.0, 1.0);
+    }
+    (p0 + d0 * s, q0 + d1 * t)
+}
+
+// Sutherland-Hodgman: 保留 dot(normal, p) <= offset 的部分
+fn clip_polygon(points: &[Vec3], normal: Vec3, offset: f32) -> Vec<Vec3> {
+    let mut clipped = Vec::with_capacity(points.len() + 1);
+    for i in 0..points.len() {
+        let p0 = points[i];
+        let p1 = points[(i + 1) % points.len()];
+        let d0 = normal.dot(p0) - offset;
+        let d1 = normal.dot(p1) - offset;
+        if d0 <= 0.0 {
+            clipped.push(p0);
+        }
+        if (d0 < 0.0 && d1 > 0.0) || (d0 > 0.0 && d1 < 0.0) {
+            clipped.push(p0 + (p1 - p0) * (d0 / (d0 - d1)));
+        }
+    }
+    clipped
+}
+
+// 用参考面的侧面裁剪入射面, 返回入射面上低于参考面的点及其深度
+fn clip_faces(reference: &ConvexPolyhedron, face: usize, incident: &ConvexPolyhedron) -> Vec<(Vec3, f32)> {
+    let (ref_normal, ref_offset) = reference.planes[face];
+    let ref_face = &reference.faces[face];
+    let center = ref_face.iter().map(|i| reference.vertices[*i]).sum::<Vec3>() / ref_face.len() as f32;
+
+    let inc_face = &incident.faces[incident.incident_face(ref_normal)];
+    let mut points: Vec<Vec3> = inc_face.iter().map(|i| incident.vertices[*i]).collect();
+
+    for i in 0..ref_face.len() {
+        let v0 = reference.vertices[ref_face[i]];
+        let v1 = reference.vertices[ref_face[(i + 1) % ref_face.len()]];
+        let mut side = (v1 - v0).cross(ref_normal).normalize_or_zero();
+        if side.dot(center - v0) > 0.0 {
+            side = -side;
+        }
+        points = clip_polygon(&points, side, side.dot(v0));
+        if points.is_empty() {
+            break;
+        }
+    }
+
+    points.into_iter()
+        .map(|p| (p, ref_offset - ref_normal.dot(p)))
+        .filter(|(_, depth)| *depth >= 0.0)
+        .collect()
+}
+
+pub fn collide(a: &ConvexPolyhedron, b: &ConvexPolyhedron, contacts: &mut Vec<ContactPoint>) -> bool {
+    let Some((normal, depth, feature)) = separating_axis(a, b) else {
+        return false;
+    };
+    let count = contacts.len();
+
+    match feature {
+        Feature::FaceA => {
+            let face = a.incident_face(-normal);
+            let ref_normal = a.planes[face].0;
+            for (p, d) in clip_faces(a, face, b) {
+                contacts.push(ContactPoint { normal: ref_normal, pos_a: p + ref_normal * d, pos_b: p });
+            }
+        }
+        Feature::FaceB => {
+            let face = b.incident_face(normal);
+            let ref_normal = b.planes[face].0;
+            for (p, d) in clip_faces(b, face, a) {
+                contacts.push(ContactPoint { normal: -ref_normal, pos_a: p, pos_b: p + ref_normal * d });
+            }
+        }
+        Feature::Edges(ia, ib) => {
+            let ea = a.edges[ia];
+            let eb = b.edges[ib];
+            let (a0, a1) = a.support_edge(a.vertices[ea.1] - a.vertices[ea.0], normal);
+            let (b0, b1) = b.support_edge(b.vertices[eb.1] - b.vertices[eb.0], -normal);
+            let (pos_a, pos_b) = closest_points_on_segments(a0, a1, b0, b1);
+            if (pos_a - pos_b).dot(normal) > 0.0 {
+                contacts.push(ContactPoint { normal, pos_a, pos_b });
+            }
+        }
+    }
+
+    if contacts.len() == count {
+        // 特征裁剪失败时退化为支撑点
+        let mut support = a.vertices[0];
+        for v in a.vertices.iter() {
+            if v.dot(normal) > support.dot(normal) {
+                support = *v;
+            }
+        }
+        contacts.push(ContactPoint { normal, pos_a: support, pos_b: support - normal * depth });
+    }
+    true
+}
+
+// 球和胶囊的核心点(线段)的世界坐标, 球的两个端点重合
+fn round_core(body: &RigidBody, part: &ConvexPart) -> (Vec3, Vec3) {
+    let vertices = &part.vertices;
+    (body.local_to_world(vertices[0]), body.local_to_world(vertices[vertices.len() - 1]))
+}
+
+// 球和胶囊: 核心点/线段之间的最近点, 距离小于两半径之和即接触
+fn collide_round(a: &RigidBody, part_a: &ConvexPart, b: &RigidBody, part_b: &ConvexPart, contacts: &mut Vec<ContactPoint>) -> bool {
+    let (a0, a1) = round_core(a, part_a);
+    let (b0, b1) = round_core(b, part_b);
+    let (p, q) = closest_points_on_segments(a0, a1, b0, b1);
+    let d = q - p;
+    let distance = d.length();
+    if distance >= part_a.radius + part_b.radius {
+        return false;
+    }
+    let normal = if distance > 1.0e-6 { d / distance } else { Vec3::Y };
+    contacts.push(ContactPoint { normal, pos_a: p + normal * part_a.radius, pos_b: q - normal * part_b.radius });
+    true
+}
+
+// Cyrus-Beck: 线段裁剪到多面体内部后是否还有剩余
+fn segment_intersects(poly: &ConvexPolyhedron, p0: Vec3, p1: Vec3) -> bool {
+    let d = p1 - p0;
+    let mut t0 = 0.0_f32;
+    let mut t1 = 1.0_f32;
+    for (normal, offset) in poly.planes.iter() {
+        let dist = normal.dot(p0) - offset;
+        let denom = normal.dot(d);
+        if denom.abs() < 1.0e-8 {
+            if dist > 0.0 {
+                return false;
+            }
+            continue;
+        }
+        let t = -dist / denom;
+        if denom < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+        if t0 > t1 {
+            return false;
+        }
+    }
+    true
+}
+
+// 面上离 p 最近的点: 投影落在面内时取投影, 否则取面的棱上的最近点
+fn closest_point_on_face(poly: &ConvexPolyhedron, face: usize, p: Vec3) -> Vec3 {
+    let (normal, offset) = poly.planes[face];
+    let indices = &poly.faces[face];
+    let proj = p - normal * (normal.dot(p) - offset);
+    let mut inside = true;
+    let mut sign = 0.0_f32;
+    for i in 0..indices.len() {
+        let v0 = poly.vertices[indices[i]];
+        let v1 = poly.vertices[indices[(i + 1) % indices.len()]];
+        let side = (v1 - v0).cross(proj - v0).dot(normal);
+        if side * sign < 0.0 {
+            inside = false;
+            break;
+        }
+        if side != 0.0 {
+            sign = side;
+        }
+    }
+    if inside {
+        return proj;
+    }
+    let mut best = proj;
+    let mut best_d = f32::MAX;
+    for i in 0..indices.len() {
+        let v0 = poly.vertices[indices[i]];
+        let v1 = poly.vertices[indices[(i + 1) % indices.len()]];
+        let (_, q) = closest_points_on_segments(p, p, v0, v1);
+        let d = q.distance_squared(p);
+        if d < best_d {
+            best_d = d;
+            best = q;
+        }
+    }
+    best
+}
+
+// 多面体外的点 p 在多面体表面上的最近点, 只需要看 p 位于外侧的面
+fn closest_point_on_polyhedron(poly: &ConvexPolyhedron, p: Vec3) -> Vec3 {
+    let mut best = p;
+    let mut best_d = f32::MAX;
+    for (face, (normal, offset)) in poly.planes.iter().enumerate() {
+        if normal.dot(p) <= *offset {
+            continue;
+        }
+        let q = closest_point_on_face(poly, face, p);
+        let d = q.distance_squared(p);
+        if d < best_d {
+            best_d = d;
+            best = q;
+        }
+    }
+    best
+}
+
+// 多面体与球/胶囊, 返回 (法线, 多面体上的点, 球/胶囊表面上的点), 法线从多面体指向球/胶囊
+// 核心不在多面体内时, 求核心到多面体的最近点再膨胀一个半径; 胶囊的两个端点各自生成接触, 平躺时有两个支撑点
+// 核心穿入多面体时, 按分离轴取穿透最浅的面, 核心上低于该面 radius 以内的端点各生成接触
+fn collide_polyhedron_round(poly: &ConvexPolyhedron, c0: Vec3, c1: Vec3, radius: f32) -> Vec<(Vec3, Vec3, Vec3)> {
+    let mut points = Vec::new();
+    let is_sphere = c0 == c1;
+    let ends: &[Vec3] = if is_sphere { &[c0][..] } else { &[c0, c1][..] };
+
+    if !segment_intersects(poly, c0, c1) {
+        let push = |c: Vec3, q: Vec3, points: &mut Vec<(Vec3, Vec3, Vec3)>| {
+            let d = c - q;
+            let distance = d.length();
+            if distance < radius && distance > 1.0e-6 {
+                let normal = d / distance;
+                points.push((normal, q, c - normal * radius));
+            }
+        };
+        for c in ends.iter() {
+            push(*c, closest_point_on_polyhedron(poly, *c), &mut points);
+        }
+        if points.is_empty() && !is_sphere {
+            // 端点都不接触时, 胶囊中段可能搭在多面体的棱上
+            let mut best = (c0, c0);
+            let mut best_d = f32::MAX;
+            for (i0, i1) in poly.edges.iter() {
+                let (p, q) = closest_points_on_segments(c0, c1, poly.vertices[*i0], poly.vertices[*i1]);
+                let d = p.distance_squared(q);
+                if d < best_d {
+                    best_d = d;
+                    best = (p, q);
+                }
+            }
+            push(best.0, best.1, &mut points);
+        }
+        return points;
+    }
+
+    let mut best: Option<(Vec3, f32, f32)> = None;
+    for (normal, offset) in poly.planes.iter() {
+        let depth = offset - ends.iter().map(|c| normal.dot(*c)).fold(f32::MAX, f32::min);
+        match best {
+            Some((_, _, best_depth)) if depth >= best_depth => {}
+            _ => best = Some((*normal, *offset, depth)),
+        }
+    }
+    let Some((normal, offset, _)) = best else {
+        return points;
+    };
+    for c in ends.iter() {
+        let height = normal.dot(*c) - offset;
+        if height < radius {
+            points.push((normal, *c - normal * height, *c - normal * radius));
+        }
+    }
+    points
+}
+
+// 对两刚体的每对凸块按类型分派窄相检测
+pub fn collide_bodies(a: &RigidBody, b: &RigidBody, contacts: &mut Vec<ContactPoint>) -> bool {
+    let mut hit = false;
+    for part_a in a.get_parts().iter() {
+        for part_b in b.get_parts().iter() {
+            hit |= match (part_a.is_polyhedron(), part_b.is_polyhedron()) {
+                (true, true) => collide(&ConvexPolyhedron::from_part(a, part_a), &ConvexPolyhedron::from_part(b, part_b), contacts),
+                (false, false) => collide_round(a, part_a, b, part_b, contacts),
+                (true, false) => {
+                    let (c0, c1) = round_core(b, part_b);
+                    let points = collide_polyhedron_round(&ConvexPolyhedron::from_part(a, part_a), c0, c1, part_b.radius);
+                    for (normal, pos_a, pos_b) in points.iter() {
+                        contacts.push(ContactPoint { normal: *normal, pos_a: *pos_a, pos_b: *pos_b });
+                    }
+                    !points.is_empty()
+                }
+                (false, true) => {
+                    let (c0, c1) = round_core(a, part_a);
+                    let points = collide_polyhedron_round(&ConvexPolyhedron::from_part(b, part_b), c0, c1, part_a.radius);
+                    for (normal, pos_b, pos_a) in points.iter() {
+                        contacts.push(ContactPoint { normal: -*normal, pos_a: *pos_a, pos_b: *pos_b });
+                    }
+                    !points.is_empty()
+                }
+            };
+        }
+    }
+    hit
+}
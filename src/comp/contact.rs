@@ -0,0 +1,242 @@
+use crate::comp::arena::Arena;
+use crate::comp::collision::{collide_bodies, ContactPoint};
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// 地面: 所有满足 dot(x, normal) = height 的点构成的平面
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GroundPlane {
+    pub normal: Vec3,
+    pub height: f32,
+    pub compliance: f32,
+    pub restitution: f32,
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+}
+
+impl Default for GroundPlane {
+    fn default() -> Self {
+        GroundPlane {
+            normal: Vec3::Y,
+            height: 0.0,
+            compliance: 0.0,
+            restitution: 0.0,
+            static_friction: 0.5,
+            dynamic_friction: 0.3,
+        }
+    }
+}
+
+impl GroundPlane {
+    pub fn origin(&self) -> Vec3 {
+        self.normal * self.height
+    }
+
+    pub fn depth(&self, world_pos: Vec3) -> f32 {
+        self.height - world_pos.dot(self.normal)
+    }
+
+    // 穿透地面的顶点各生成一个接触, 地面一侧的接触点固定在平面上
+    // 球和胶囊的顶点是核心点, 沿法线向下偏移一个半径得到表面上的接触点
+    pub fn collect_contacts(&self, bodies: &Arena<RigidBody>, contacts: &mut Vec<Contact>) {
+        for (handle, body) in bodies.iter() {
+            if body.is_static() {
+                continue;
+            }
+            for (part, v) in body.get_parts().iter().flat_map(|p| p.vertices.iter().map(move |v| (p, v))) {
+                let world_pos = body.local_to_world(*v) - self.normal * part.radius;
+                let depth = self.depth(world_pos);
+                if depth <= 0.0 {
+                    continue;
+                }
+                contacts.push(Contact {
+                    body0: handle,
+                    body1: None,
+                    local_pos0: body.world_to_local(world_pos),
+                    local_pos1: world_pos + self.normal * depth,
+                    normal: -self.normal,
+                    compliance: self.compliance,
+                    restitution: 0.5 * (body.get_restitution() + self.restitution),
+                    static_friction: 0.5 * (body.get_static_friction() + self.static_friction),
+                    dynamic_friction: 0.5 * (body.get_dynamic_friction() + self.dynamic_friction),
+                    normal_force: 0.0,
+                    normal_vel: 0.0,
+                });
+            }
+        }
+    }
+}
+
+// 单边接触约束, normal 从 body0 指向 body1; body1 为 None 时 local_pos1 即世界坐标
+pub struct Contact {
+    pub body0: BodyHandle,
+    pub body1: Option<BodyHandle>,
+    pub local_pos0: Vec3,
+    pub local_pos1: Vec3,
+    pub normal: Vec3,
+    pub compliance: f32,
+    pub restitution: f32,
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub normal_force: f32,
+    // 位置求解前接触点沿法线的相对速度, 正值表示相互接近
+    pub normal_vel: f32,
+}
+
+impl Contact {
+    pub fn new(body0: BodyHandle, body_0: &RigidBody, body1: BodyHandle, body_1: &RigidBody, point: &ContactPoint) -> Self {
+        Self {
+            body0,
+            body1: Some(body1),
+            local_pos0: body_0.world_to_local(point.pos_a),
+            local_pos1: body_1.world_to_local(point.pos_b),
+            normal: point.normal,
+            compliance: 0.0,
+            restitution: 0.5 * (body_0.get_restitution() + body_1.get_restitution()),
+            static_friction: 0.5 * (body_0.get_static_friction() + body_1.get_static_friction()),
+            dynamic_friction: 0.5 * (body_0.get_dynamic_friction() + body_1.get_dynamic_friction()),
+            normal_force: 0.0,
+            normal_vel: 0.0,
+        }
+    }
+
+    fn with_bodies<F>(&mut self, bodies: &mut Arena<RigidBody>, f: F)
+    where
+        F: FnOnce(&mut Self, &mut RigidBody, Option<&mut RigidBody>),
+    {
+        if let Some((body_0, body_1)) = get_bodies(bodies, self.body0, self.body1) {
+            f(self, body_0, body_1);
+        }
+    }
+
+    fn world_pos1(&self, body_1: &Option<&mut RigidBody>) -> Vec3 {
+        match body_1 {
+            Some(body_1) => body_1.local_to_world(self.local_pos1),
+            None => self.local_pos1,
+        }
+    }
+
+    fn prev_world_pos1(&self, body_1: &Option<&mut RigidBody>) -> Vec3 {
+        match body_1 {
+            Some(body_1) => body_1.prev_local_to_world(self.local_pos1),
+            None => self.local_pos1,
+        }
+    }
+
+    fn relative_velocity(&self, body_0: &RigidBody, body_1: &Option<&mut RigidBody>, world_pos0: Vec3, world_pos1: Vec3) -> Vec3 {
+        let mut v = body_0.get_point_velocity(world_pos0);
+        if let Some(body_1) = body_1 {
+            v -= body_1.get_point_velocity(world_pos1);
+        }
+        v
+    }
+
+    // 多次迭代时法向力累加, 摩擦用的是整个子步的法向力
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        self.with_bodies(bodies, |contact, body_0, body_1| {
+            let world_pos0 = body_0.local_to_world(contact.local_pos0);
+            let world_pos1 = contact.world_pos1(&body_1);
+            let depth = (world_pos0 - world_pos1).dot(contact.normal);
+            if depth <= 0.0 {
+                return;
+            }
+            contact.normal_vel = contact.relative_velocity(body_0, &body_1, world_pos0, world_pos1).dot(contact.normal);
+            let force = body_0.apply_correction(contact.compliance, -contact.normal * depth, world_pos0,
+                                                  body_1, world_pos1, dt);
+            contact.normal_force += force.abs();
+        });
+    }
+
+    // 静摩擦: 撤销接触点本子步内的切向相对位移, 前提是所需的力不超过 μs * fn
+    pub fn solve_friction(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.normal_force == 0.0 {
+            return;
+        }
+        self.with_bodies(bodies, |contact, body_0, body_1| {
+            let world_pos0 = body_0.local_to_world(contact.local_pos0);
+            let world_pos1 = contact.world_pos1(&body_1);
+            let prev_pos0 = body_0.prev_local_to_world(contact.local_pos0);
+            let prev_pos1 = contact.prev_world_pos1(&body_1);
+            let mut d_p = (world_pos0 - prev_pos0) - (world_pos1 - prev_pos1);
+            d_p -= contact.normal * d_p.dot(contact.normal);
+            let c = d_p.length();
+            if c == 0.0 {
+                return;
+            }
+            let tangent = d_p / c;
+            let mut w = body_0.get_mass_inv(tangent, world_pos0);
+            if let Some(ref body_1) = body_1 {
+                w += body_1.get_mass_inv(tangent, world_pos1);
+            }
+            if w == 0.0 {
+                return;
+            }
+            let tangent_force = c / w / dt / dt;
+            if tangent_force < contact.static_friction * contact.normal_force {
+                body_0.apply_correction(0.0, -d_p, world_pos0, body_1, world_pos1, dt);
+            }
+        });
+    }
+
+    // 动摩擦: update_vel 之后在速度层面削减切向相对速度
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.normal_force == 0.0 {
+            return;
+        }
+        self.with_bodies(bodies, |contact, body_0, body_1| {
+            let world_pos0 = body_0.local_to_world(contact.local_pos0);
+            let world_pos1 = contact.world_pos1(&body_1);
+            let v = contact.relative_velocity(body_0, &body_1, world_pos0, world_pos1);
+            let v_t = v - contact.normal * v.dot(contact.normal);
+            let v_t_len = v_t.length();
+            if v_t_len == 0.0 {
+                return;
+            }
+            let tangent = v_t / v_t_len;
+            let mut w = body_0.get_mass_inv(tangent, world_pos0);
+            if let Some(ref body_1) = body_1 {
+                w += body_1.get_mass_inv(tangent, world_pos1);
+            }
+            // 摩擦冲量上限 dt * μd * fn, 乘以 w 换算为接触点的速度变化
+            let d_v = -tangent * (dt * contact.dynamic_friction * contact.normal_force * w).min(v_t_len);
+            body_0.apply_velocity_correction(d_v, world_pos0, body_1, world_pos1);
+        });
+    }
+
+    // 恢复系数: 把法向相对速度改为 -e * 碰撞前法向速度; 速度很小时 e 取 0, 避免静止接触抖动
+    pub fn solve_restitution(&mut self, bodies: &mut Arena<RigidBody>, dt: f32, gravity: f32) {
+        if self.normal_force == 0.0 {
+            return;
+        }
+        self.with_bodies(bodies, |contact, body_0, body_1| {
+            let world_pos0 = body_0.local_to_world(contact.local_pos0);
+            let world_pos1 = contact.world_pos1(&body_1);
+            let v_n = contact.relative_velocity(body_0, &body_1, world_pos0, world_pos1).dot(contact.normal);
+            let restitution = if contact.normal_vel.abs() <= 2.0 * gravity * dt { 0.0 } else { contact.restitution };
+            let target = (-restitution * contact.normal_vel).min(0.0);
+            let d_v = contact.normal * (target - v_n);
+            body_0.apply_velocity_correction(d_v, world_pos0, body_1, world_pos1);
+        });
+    }
+}
+
+// 对宽相给出的候选对做窄相检测
+pub fn collect_contacts(bodies: &Arena<RigidBody>,
+                        pairs: &[(BodyHandle, BodyHandle)],
+                        contacts: &mut Vec<Contact>) {
+    contacts.clear();
+
+    let mut points = Vec::new();
+    for (handle_0, handle_1) in pairs.iter() {
+        if let (Some(body_0), Some(body_1)) = (bodies.get(*handle_0), bodies.get(*handle_1)) {
+            points.clear();
+            if !collide_bodies(body_0, body_1, &mut points) {
+                continue;
+            }
+            for point in points.iter() {
+                contacts.push(Contact::new(*handle_0, body_0, *handle_1, body_1, point));
+            }
+        }
+    }
+}
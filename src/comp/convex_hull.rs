@@ -0,0 +1,278 @@
+use glam::{Mat3, Vec3};
+
+// 凸包, 顶点以质心为原点; inertia 为单位密度下绕质心的转动惯量张量
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvexHull {
+    pub vertices: Vec<Vec3>,
+    // 每个面的顶点下标按从外侧看逆时针排列, 共面的三角形已合并为多边形
+    pub faces: Vec<Vec<usize>>,
+    pub volume: f32,
+    pub inertia: Mat3,
+    // 质心在输入点坐标系下的位置
+    pub center: Vec3,
+}
+
+// 四面体 (0, a, b, c) 在单位行列式下的二阶矩 ∫ x x^T
+const CANONICAL_COVARIANCE: Mat3 = Mat3::from_cols_array(&[
+    2.0 / 120.0, 1.0 / 120.0, 1.0 / 120.0,
+    1.0 / 120.0, 2.0 / 120.0, 1.0 / 120.0,
+    1.0 / 120.0, 1.0 / 120.0, 2.0 / 120.0,
+]);
+
+fn outer(a: Vec3, b: Vec3) -> Mat3 {
+    Mat3::from_cols(a * b.x, a * b.y, a * b.z)
+}
+
+fn triangle_normal(points: &[Vec3], face: &[usize; 3]) -> Vec3 {
+    let a = points[face[0]];
+    (points[face[1]] - a).cross(points[face[2]] - a)
+}
+
+// 多边形各边叉积之和, 不受相邻顶点共线的影响
+pub fn face_normal(vertices: &[Vec3], face: &[usize]) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    for k in 0..face.len() {
+        normal += vertices[face[k]].cross(vertices[face[(k + 1) % face.len()]]);
+    }
+    normal.normalize_or_zero()
+}
+
+impl ConvexHull {
+    // 增量法求凸包, 点数不足或所有点共面时返回 None
+    pub fn from_points(points: &[Vec3]) -> Option<ConvexHull> {
+        if points.len() < 4 {
+            return None;
+        }
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        for p in points.iter() {
+            min = min.min(*p);
+            max = max.max(*p);
+        }
+        let eps = 1.0e-5 * (max - min).max_element().max(1.0e-6);
+
+        // 初始四面体: 最远的两点, 离该直线最远的点, 离该平面最远的点
+        let i0 = 0;
+        let i1 = (0..points.len())
+            .max_by(|a, b| points[*a].distance_squared(points[i0]).total_cmp(&points[*b].distance_squared(points[i0])))?;
+        let dir = (points[i1] - points[i0]).normalize_or_zero();
+        let line_distance = |p: Vec3| (p - points[i0]).reject_from_normalized(dir).length();
+        let i2 = (0..points.len())
+            .max_by(|a, b| line_distance(points[*a]).total_cmp(&line_distance(points[*b])))?;
+        let normal = (points[i1] - points[i0]).cross(points[i2] - points[i0]).normalize_or_zero();
+        let plane_distance = |p: Vec3| (p - points[i0]).dot(normal).abs();
+        let i3 = (0..points.len())
+            .max_by(|a, b| plane_distance(points[*a]).total_cmp(&plane_distance(points[*b])))?;
+        if line_distance(points[i2]) <= eps || plane_distance(points[i3]) <= eps {
+            return None;
+        }
+
+        let interior = (points[i0] + points[i1] + points[i2] + points[i3]) * 0.25;
+        let mut triangles: Vec<[usize; 3]> = Vec::new();
+        for face in [[i0, i1, i2], [i0, i1, i3], [i0, i2, i3], [i1, i2, i3]] {
+            if triangle_normal(points, &face).dot(points[face[0]] - interior) < 0.0 {
+                triangles.push([face[0], face[2], face[1]]);
+            } else {
+                triangles.push(face);
+            }
+        }
+
+        for (i, p) in points.iter().enumerate() {
+            let visible: Vec<bool> = triangles.iter()
+                .map(|t| {
+                    let n = triangle_normal(points, t).normalize_or_zero();
+                    n.dot(*p - points[t[0]]) > eps
+                })
+                .collect();
+            if !visible.iter().any(|v| *v) {
+                continue;
+            }
+
+            // 可见面与不可见面之间的边构成地平线, 沿用可见面中的方向以保持环绕顺序
+            let mut horizon = Vec::new();
+            for (t, _) in triangles.iter().zip(visible.iter()).filter(|(_, v)| **v) {
+                for k in 0..3 {
+                    let (a, b) = (t[k], t[(k + 1) % 3]);
+                    let shared = triangles.iter().zip(visible.iter())
+                        .any(|(o, v)| *v && (0..3).any(|m| o[m] == b && o[(m + 1) % 3] == a));
+                    if !shared {
+                        horizon.push((a, b));
+                    }
+                }
+            }
+
+            let mut kept = Vec::with_capacity(triangles.len());
+            for (t, v) in triangles.iter().zip(visible.iter()) {
+                if !*v {
+                    kept.push(*t);
+                }
+            }
+            for (a, b) in horizon {
+                kept.push([a, b, i]);
+            }
+            triangles = kept;
+        }
+
+        Some(Self::from_triangles(points, &triangles, eps))
+    }
+
+    fn from_triangles(points: &[Vec3], triangles: &[[usize; 3]], eps: f32) -> ConvexHull {
+        // 体积、质心和二阶矩: 以任一内部点为顶点把凸包剖分成四面体后累加
+        let reference = triangles.iter()
+            .map(|t| points[t[0]] + points[t[1]] + points[t[2]])
+            .sum::<Vec3>() / (3 * triangles.len()) as f32;
+        let mut volume = 0.0;
+        let mut first_moment = Vec3::ZERO;
+        let mut covariance = Mat3::ZERO;
+        for t in triangles.iter() {
+            let a = points[t[0]] - reference;
+            let b = points[t[1]] - reference;
+            let c = points[t[2]] - reference;
+            let basis = Mat3::from_cols(a, b, c);
+            let det = basis.determinant();
+            volume += det / 6.0;
+            first_moment += (a + b + c) * det / 24.0;
+            covariance += basis * CANONICAL_COVARIANCE * basis.transpose() * det;
+        }
+        let offset = first_moment / volume;
+        covariance -= outer(offset, offset) * volume;
+        let trace = covariance.x_axis.x + covariance.y_axis.y + covariance.z_axis.z;
+        let inertia = Mat3::from_diagonal(Vec3::splat(trace)) - covariance;
+        let center = reference + offset;
+
+        // 只保留凸包上的顶点, 并平移到以质心为原点
+        let mut remap = vec![usize::MAX; points.len()];
+        let mut vertices = Vec::new();
+        for t in triangles.iter() {
+            for i in t.iter() {
+                if remap[*i] == usize::MAX {
+                    remap[*i] = vertices.len();
+                    vertices.push(points[*i] - center);
+                }
+            }
+        }
+
+        // 法线和偏移都相同的三角形属于同一个面, 面上的顶点按绕法线的角度排序
+        let mut planes: Vec<(Vec3, f32, Vec<usize>)> = Vec::new();
+        for t in triangles.iter() {
+            let t = [remap[t[0]], remap[t[1]], remap[t[2]]];
+            let normal = triangle_normal(&vertices, &t).normalize_or_zero();
+            let offset = normal.dot(vertices[t[0]]);
+            let plane = planes.iter_mut()
+                .find(|(n, d, _)| n.dot(normal) > 1.0 - 1.0e-4 && (d - offset).abs() <= eps);
+            match plane {
+                Some((_, _, indices)) => {
+                    for i in t {
+                        if !indices.contains(&i) {
+                            indices.push(i);
+                        }
+                    }
+                }
+                None => planes.push((normal, offset, t.to_vec())),
+            }
+        }
+        let faces = planes.into_iter()
+            .map(|(normal, _, mut indices)| {
+                let centroid = indices.iter().map(|i| vertices[*i]).sum::<Vec3>() / indices.len() as f32;
+                let u = (vertices[indices[0]] - centroid).normalize_or_zero();
+                let w = normal.cross(u);
+                let angle = |i: &usize| {
+                    let d = vertices[*i] - centroid;
+                    d.dot(w).atan2(d.dot(u))
+                };
+                indices.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+                indices
+            })
+            .collect();
+
+        ConvexHull { vertices, faces, volume, inertia, center }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec3;
+
+    fn unit_cube(center: Vec3) -> Vec<Vec3> {
+        let mut points = Vec::new();
+        for x in [-0.5, 0.5] {
+            for y in [-0.5, 0.5] {
+                for z in [-0.5, 0.5] {
+                    points.push(center + vec3(x, y, z));
+                }
+            }
+        }
+        points
+    }
+
+    fn assert_mat3_near(a: Mat3, b: Mat3, eps: f32) {
+        assert!(a.abs_diff_eq(b, eps), "{a} != {b}");
+    }
+
+    #[test]
+    fn unit_cube_mass_properties() {
+        let hull = ConvexHull::from_points(&unit_cube(Vec3::ZERO)).unwrap();
+        assert_eq!(hull.vertices.len(), 8);
+        assert_eq!(hull.faces.len(), 6);
+        assert!(hull.faces.iter().all(|f| f.len() == 4));
+        assert!((hull.volume - 1.0).abs() < 1e-5);
+        assert!(hull.center.abs_diff_eq(Vec3::ZERO, 1e-5));
+        // 单位密度下质量为 1, 绕质心 m/6·I
+        assert_mat3_near(hull.inertia, Mat3::from_diagonal(Vec3::splat(1.0 / 6.0)), 1e-5);
+    }
+
+    #[test]
+    fn translated_cube_keeps_inertia_about_centroid() {
+        let center = vec3(3.0, -2.0, 5.0);
+        let hull = ConvexHull::from_points(&unit_cube(center)).unwrap();
+        assert!(hull.center.abs_diff_eq(center, 1e-4));
+        assert_mat3_near(hull.inertia, Mat3::from_diagonal(Vec3::splat(1.0 / 6.0)), 1e-4);
+    }
+
+    #[test]
+    fn offset_tetrahedron_centroid() {
+        let origin = vec3(1.0, 2.0, 3.0);
+        let points = [origin, origin + vec3(2.0, 0.0, 0.0), origin + vec3(0.0, 2.0, 0.0), origin + vec3(0.0, 0.0, 2.0)];
+        let hull = ConvexHull::from_points(&points).unwrap();
+        assert!((hull.volume - 8.0 / 6.0).abs() < 1e-5);
+        assert!(hull.center.abs_diff_eq(origin + Vec3::splat(0.5), 1e-5), "{}", hull.center);
+        assert_eq!(hull.faces.len(), 4);
+        // 顶点以质心为原点
+        let mean = hull.vertices.iter().copied().sum::<Vec3>() / 4.0;
+        assert!(mean.abs_diff_eq(Vec3::ZERO, 1e-5));
+    }
+
+    #[test]
+    fn interior_points_are_dropped() {
+        let mut points = unit_cube(Vec3::ZERO);
+        points.insert(0, Vec3::ZERO);
+        points.push(vec3(0.1, -0.2, 0.3));
+        points.push(vec3(-0.4, 0.4, 0.0));
+        // 面上的点也不是顶点
+        points.push(vec3(0.5, 0.1, 0.1));
+        let hull = ConvexHull::from_points(&points).unwrap();
+        assert_eq!(hull.vertices.len(), 8);
+        assert!(hull.vertices.iter().all(|v| v.abs().abs_diff_eq(Vec3::splat(0.5), 1e-5)));
+        assert!((hull.volume - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn degenerate_inputs_return_none() {
+        let too_few = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        assert!(ConvexHull::from_points(&too_few).is_none());
+        assert!(ConvexHull::from_points(&[]).is_none());
+
+        let coincident = [Vec3::ONE; 5];
+        assert!(ConvexHull::from_points(&coincident).is_none());
+
+        let collinear: Vec<Vec3> = (0..6).map(|i| vec3(1.0, 2.0, 3.0) * i as f32).collect();
+        assert!(ConvexHull::from_points(&collinear).is_none());
+
+        let coplanar: Vec<Vec3> = (0..8).map(|i| {
+            let a = i as f32 * 0.8;
+            vec3(a.cos(), 0.5 * a.cos() + 0.3 * a.sin(), a.sin())
+        }).collect();
+        assert!(ConvexHull::from_points(&coplanar).is_none());
+    }
+}
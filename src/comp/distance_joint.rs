@@ -1,106 +1,188 @@
-use crate::comp::rigidbody::{CuboidBody, Simulate};
-use bevy::prelude::*;
-#[derive(Component)]
+use crate::comp::arena::Arena;
+use crate::comp::joint::{sanitize_limits, world_point, Breakable, BreakState, JointSensor};
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// 距离约束的工作方式, distance 为静止长度
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DistanceMode {
+    // 绳子: 只在长度超过 distance 时拉回
+    Rope,
+    // 刚性杆: 长度保持 distance
+    Rod,
+    // 弹簧: 刚度单位 N/m, 阻尼单位 N·s/m, 忽略 compliance
+    Spring { stiffness: f32, damping: f32 },
+    // 长度限制在 [min, max] 之间, 忽略 distance
+    Range { min: f32, max: f32 },
+}
+
 pub struct DistanceConstraint {
-    body0: Entity,
-    body1: Option<Entity>,
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
     world_pos0: Vec3,
     world_pos1: Vec3,
     local_pos0: Vec3,
     local_pos1: Vec3,
     distance: f32,
     compliance: f32,
-    unilateral: bool,
+    mode: DistanceMode,
     corr: Vec3,
+    break_state: BreakState,
+    // 最近一个子步结束时的长度和伸长速度
+    length: f32,
+    axial_velocity: f32,
 }
 
 impl DistanceConstraint{
+    // local_pos0 为 body0 上的局部坐标; local_pos1 为 body1 上的局部坐标, body1 为 None 时是世界坐标
     pub fn new(
-        body0: Entity,
-        body1: Option<Entity>,
-        pos0: Vec3,
-        pos1: Vec3,
+        body0: BodyHandle,
+        body1: Option<BodyHandle>,
+        local_pos0: Vec3,
+        local_pos1: Vec3,
         distance: f32,
         compliance: f32,
-        unilateral: bool,
-        query: &mut Query<(&mut CuboidBody, &mut Transform)>
+        mode: DistanceMode,
     ) -> Self {
-        let mut local_pos0 = pos0;
-        let mut local_pos1 = pos1;
-        if let Ok((cuboid_0, _transform)) = query.get(body0) {
-            local_pos0 = cuboid_0.world_to_local(pos0);
-        }
-
-        if let Some(ref body_1_ref) = body1 {
-            if let Ok((cuboid_1, _transform)) = query.get(*body_1_ref) {
-                local_pos1 = cuboid_1.world_to_local(pos1);
+        let mode = match mode {
+            DistanceMode::Range { min, max } => {
+                let (min, max) = sanitize_limits("distance range", min, max);
+                DistanceMode::Range { min, max }
             }
-        }
-
+            _ => mode,
+        };
         Self {
             body0,
             body1,
-            world_pos0: pos0,
-            world_pos1: pos1,
+            world_pos0: local_pos0,
+            world_pos1: local_pos1,
             local_pos0,
             local_pos1,
             distance,
             compliance,
-            unilateral,
+            mode,
             corr: Vec3::ZERO,
+            break_state: BreakState::default(),
+            length: distance,
+            axial_velocity: 0.0,
         }
     }
 
-    pub fn solve(&mut self, query: &mut Query<(Entity, &mut CuboidBody, &mut Transform)>) {
-        let mut cuboid_0_ins = None;
-        let mut cuboid_1_ins = None;
+    // 当前长度, 张力和伸长速度
+    pub fn get_sensor(&self) -> JointSensor {
+        JointSensor {
+            length: self.length,
+            tension: self.break_state.force,
+            axial_velocity: self.axial_velocity,
+        }
+    }
 
-        for (entity, cuboid_body, _transform) in query.iter_mut() {
-            if entity == self.body0 {
-                cuboid_0_ins = Some(cuboid_body);
-            }
-            else{
-                if let Some(body_1_ref) = self.body1 {
-                    if entity == body_1_ref {
-                        self.world_pos1 = cuboid_body.local_to_world(self.local_pos1);
-                        cuboid_1_ins = Some(cuboid_body);
-                    }
-                }
-            }
+    // 最近一次求解时两个锚点的世界坐标
+    pub fn get_world_positions(&self) -> (Vec3, Vec3) {
+        (self.world_pos0, self.world_pos1)
+    }
 
-        }
-        if cuboid_0_ins.is_none() {return;}
-        let mut cuboid_0_ins = cuboid_0_ins.unwrap();
+    // 创建时的参数: 两个锚点的局部坐标, 静止长度, 柔度和工作方式
+    pub fn get_local_positions(&self) -> (Vec3, Vec3) {
+        (self.local_pos0, self.local_pos1)
+    }
 
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
 
-        self.world_pos0 = cuboid_0_ins.local_to_world(self.local_pos0);
+    pub fn get_compliance(&self) -> f32 {
+        self.compliance
+    }
 
-        self.corr = self.world_pos1 - self.world_pos0;
-        let distance = self.corr.length();
-        self.corr = self.corr.normalize();
+    pub fn get_mode(&self) -> DistanceMode {
+        self.mode
+    }
 
-        if self.unilateral && distance <= self.distance {
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
             return;
+        };
+        if let Some(ref body_1_ins) = body_1_ins {
+            self.world_pos1 = body_1_ins.local_to_world(self.local_pos1);
         }
 
-        self.corr *= distance - self.distance;
+        self.world_pos0 = body_0_ins.local_to_world(self.local_pos0);
 
-        {
-            let _force = cuboid_0_ins.apply_correction(self.compliance, self.corr, self.world_pos0, cuboid_1_ins, self.world_pos1);
-            let _elongation = (distance - self.distance).round();
-            //print!("force: {} elongation: {} ", force, elongation);
+        self.corr = self.world_pos1 - self.world_pos0;
+        let distance = self.corr.length();
+        // 两个锚点重合时没有方向, 这一子步不修正
+        self.corr = self.corr.normalize_or_zero();
+        if self.corr == Vec3::ZERO {
+            return;
+        }
+
+        // 目标长度和柔度, 绳子松弛或长度在范围内时不需要修正
+        let (target, compliance) = match self.mode {
+            DistanceMode::Rope => (self.distance, self.compliance),
+            DistanceMode::Rod => (self.distance, self.compliance),
+            DistanceMode::Spring { stiffness, .. } => (self.distance, 1.0 / stiffness),
+            DistanceMode::Range { min, max } => (distance.clamp(min, max), self.compliance),
+        };
+        if distance == target || (self.mode == DistanceMode::Rope && distance <= target) {
+            return;
         }
 
+        self.corr *= distance - target;
 
+        let force = body_0_ins.apply_correction(compliance, self.corr, self.world_pos0, body_1_ins, self.world_pos1, dt).abs();
+        self.break_state.force += if distance > target { force } else { -force };
     }
-    pub fn show_line(&mut self, gizmos: &mut Gizmos,) {
-        gizmos.line(
-            self.world_pos0,      // 起点
-            self.world_pos1,     // 终点
-            Color::srgb_u8(255, 0, 0),      // 颜色
-        );
+
+    // 速度阶段: 记录长度和伸长速度; 弹簧阻尼减小两个端点沿弹簧方向的相对速度, 冲量不超过 dt * damping * v
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        let world_pos0 = body_0_ins.local_to_world(self.local_pos0);
+        let world_pos1 = world_point(&body_1_ins, self.local_pos1);
+        self.length = world_pos0.distance(world_pos1);
+        let normal = (world_pos1 - world_pos0).normalize_or_zero();
+        if normal == Vec3::ZERO {
+            self.axial_velocity = 0.0;
+            return;
+        }
+        let mut v = body_0_ins.get_point_velocity(world_pos0);
+        let mut w = body_0_ins.get_mass_inv(normal, world_pos0);
+        if let Some(ref body_1) = body_1_ins {
+            v -= body_1.get_point_velocity(world_pos1);
+            w += body_1.get_mass_inv(normal, world_pos1);
+        }
+        // body0 朝 body1 运动时 v_n 为正, 长度缩短
+        let mut v_n = v.dot(normal);
+        if let DistanceMode::Spring { damping, .. } = self.mode {
+            let d_v_n = -v_n * (dt * damping * w).min(1.0);
+            if d_v_n != 0.0 {
+                body_0_ins.apply_velocity_correction(normal * d_v_n, world_pos0, body_1_ins, world_pos1);
+                v_n += d_v_n;
+            }
+        }
+        self.axial_velocity = -v_n;
     }
 }
 
+impl Breakable for DistanceConstraint {
+    fn break_state(&self) -> &BreakState {
+        &self.break_state
+    }
 
+    fn break_state_mut(&mut self) -> &mut BreakState {
+        &mut self.break_state
+    }
 
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>) {
+        (self.body0, self.body1)
+    }
+}
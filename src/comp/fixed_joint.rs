@@ -0,0 +1,116 @@
+use crate::comp::arena::Arena;
+use crate::comp::joint::{rotation_error, world_point, Breakable, BreakState, JointFrame, JointSensor};
+use crate::comp::physics_world::PhysicsWorld;
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::{Quat, Vec3};
+
+// 焊接: 锁定相对位置和相对朝向, body1 为 None 时焊在世界上
+// 位置和朝向的柔度分开设置, angular_compliance 大于 0 时焊点能弯曲但不会被拉长
+pub struct FixedJoint {
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
+    // 焊点的局部坐标, 以及创建时 body0 到 body1 的相对朝向
+    frame: JointFrame,
+    compliance: f32,
+    angular_compliance: f32,
+    break_state: BreakState,
+    // 最近一个子步结束时的读数
+    sensor: JointSensor,
+    world_pos0: Vec3,
+    world_pos1: Vec3,
+}
+
+impl FixedJoint {
+    // anchor 为世界坐标, 按两个刚体当前的位姿换算到局部坐标; 焊接不用轴, 随便给一个方向
+    pub fn new(
+        physics: &PhysicsWorld,
+        body0: BodyHandle,
+        body1: Option<BodyHandle>,
+        anchor: Vec3,
+        compliance: f32,
+        angular_compliance: f32,
+    ) -> Self {
+        Self {
+            body0,
+            body1,
+            frame: JointFrame::new(physics, body0, body1, anchor, Vec3::Y),
+            compliance,
+            angular_compliance,
+            break_state: BreakState::default(),
+            sensor: JointSensor::default(),
+            world_pos0: anchor,
+            world_pos1: anchor,
+        }
+    }
+
+    // 从快照恢复时换成保存下来的局部坐标, 保留创建时的参考方向和相对朝向
+    pub fn set_frame(&mut self, frame: JointFrame) {
+        self.frame = frame;
+    }
+
+    pub fn get_frame(&self) -> JointFrame {
+        self.frame
+    }
+
+    // (位置柔度, 角度柔度)
+    pub fn get_compliances(&self) -> (f32, f32) {
+        (self.compliance, self.angular_compliance)
+    }
+
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, mut body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        // 锁定相对朝向
+        let q1 = match body_1_ins {
+            Some(ref mut body_1) => body_1.get_quat(),
+            None => Quat::IDENTITY,
+        };
+        let corr = rotation_error(body_0_ins.get_quat(), q1, self.frame.rest_rotation);
+        body_0_ins.apply_rotation_correction(self.angular_compliance, corr, body_1_ins.as_deref_mut(), dt);
+
+        // 焊点重合
+        self.world_pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        self.world_pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.break_state.force += body_0_ins.apply_correction(self.compliance, self.world_pos1 - self.world_pos0, self.world_pos0, body_1_ins, self.world_pos1, dt).abs();
+    }
+
+    // 速度阶段: 读数为两个焊点的分离和分离速度
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        let pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        let pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.sensor = JointSensor::measure(body_0_ins, &body_1_ins, pos0, pos1, (pos1 - pos0).normalize_or_zero(), self.break_state.force);
+    }
+
+    pub fn get_sensor(&self) -> JointSensor {
+        self.sensor
+    }
+
+    // 最近一次求解时两个焊点的世界坐标
+    pub fn get_world_positions(&self) -> (Vec3, Vec3) {
+        (self.world_pos0, self.world_pos1)
+    }
+}
+
+impl Breakable for FixedJoint {
+    fn break_state(&self) -> &BreakState {
+        &self.break_state
+    }
+
+    fn break_state_mut(&mut self) -> &mut BreakState {
+        &mut self.break_state
+    }
+
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>) {
+        (self.body0, self.body1)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::comp::arena::Arena;
+use crate::comp::joint::{clamp_motor_error, limit_angle, sanitize_limits, signed_angle, world_dir, world_point, wrap_angle, Breakable, BreakState, JointFrame, JointMotor, JointSensor};
+use crate::comp::physics_world::PhysicsWorld;
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+
+// 铰链: 两个锚点重合, 两个转轴对齐, body1 为 None 时连接到世界
+pub struct HingeJoint {
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
+    // 锚点, 转轴和参考方向的局部坐标, 参考方向用来计算铰链转过的角度
+    frame: JointFrame,
+    compliance: f32,
+    // 角度限制 (min, max), 单位弧度
+    limits: Option<(f32, f32)>,
+    motor: Option<JointMotor>,
+    // 上一子步结束时的转角, 速度电机用
+    prev_angle: Option<f32>,
+    break_state: BreakState,
+    // 最近一个子步结束时的读数
+    sensor: JointSensor,
+    world_pos0: Vec3,
+    world_pos1: Vec3,
+    world_axis: Vec3,
+}
+
+impl HingeJoint {
+    // anchor 和 axis 为世界坐标, 按两个刚体当前的位姿换算到局部坐标
+    pub fn new(
+        physics: &PhysicsWorld,
+        body0: BodyHandle,
+        body1: Option<BodyHandle>,
+        anchor: Vec3,
+        axis: Vec3,
+        compliance: f32,
+    ) -> Self {
+        Self {
+            body0,
+            body1,
+            frame: JointFrame::new(physics, body0, body1, anchor, axis),
+            compliance,
+            limits: None,
+            motor: None,
+            prev_angle: None,
+            break_state: BreakState::default(),
+            sensor: JointSensor::default(),
+            world_pos0: anchor,
+            world_pos1: anchor,
+            world_axis: axis.normalize(),
+        }
+    }
+
+    // 从快照恢复时换成保存下来的局部坐标, 保留创建时的参考方向和相对朝向
+    pub fn set_frame(&mut self, frame: JointFrame) {
+        self.frame = frame;
+    }
+
+    pub fn get_frame(&self) -> JointFrame {
+        self.frame
+    }
+
+    pub fn get_compliance(&self) -> f32 {
+        self.compliance
+    }
+
+    // 限制 body1 相对 body0 绕转轴转过的角度, 创建时的角度为 0
+    pub fn set_limits(&mut self, min: f32, max: f32) {
+        self.limits = Some(sanitize_limits("hinge limits", min, max));
+    }
+
+    pub fn get_limits(&self) -> Option<(f32, f32)> {
+        self.limits
+    }
+
+    // 电机驱动 body1 相对 body0 的转角, 速度单位为弧度每秒
+    pub fn set_motor(&mut self, motor: JointMotor) {
+        self.motor = Some(motor);
+    }
+
+    pub fn get_motor(&self) -> Option<JointMotor> {
+        self.motor
+    }
+
+    // body1 相对 body0 绕转轴转过的角度
+    fn get_angle(&self, body_0: &mut RigidBody, body_1: &mut Option<&mut RigidBody>) -> f32 {
+        let q0 = body_0.get_quat();
+        signed_angle(q0 * self.frame.axis0, q0 * self.frame.ref0, world_dir(body_1, self.frame.ref1))
+    }
+
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, mut body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        // 对齐转轴
+        let axis0 = body_0_ins.get_quat() * self.frame.axis0;
+        let axis1 = world_dir(&mut body_1_ins, self.frame.axis1);
+        body_0_ins.apply_rotation_correction(self.compliance, axis0.cross(axis1), body_1_ins.as_deref_mut(), dt);
+
+        // 电机: 绕转轴转动 body0 以减小转角误差, 驱动力矩不超过 max_force
+        if let Some(motor) = self.motor {
+            let angle = self.get_angle(body_0_ins, &mut body_1_ins);
+            let (error, compliance, max_force) = motor.error(angle, self.prev_angle.unwrap_or(angle), dt);
+            let axis0 = body_0_ins.get_quat() * self.frame.axis0;
+            let mut w = body_0_ins.get_angular_mass_inv(axis0);
+            if let Some(ref body_1) = body_1_ins {
+                w += body_1.get_angular_mass_inv(axis0);
+            }
+            let error = clamp_motor_error(wrap_angle(error), w, compliance, max_force, dt);
+            body_0_ins.apply_rotation_correction(compliance, axis0 * error, body_1_ins.as_deref_mut(), dt);
+        }
+
+        // 角度超出限制时, 把 body0 的参考方向转到限制角度处再与 body1 的参考方向对齐
+        if let Some((min, max)) = self.limits {
+            let q0 = body_0_ins.get_quat();
+            let ref1 = world_dir(&mut body_1_ins, self.frame.ref1);
+            if let Some(corr) = limit_angle(q0 * self.frame.axis0, q0 * self.frame.ref0, ref1, min, max) {
+                body_0_ins.apply_rotation_correction(self.compliance, corr, body_1_ins.as_deref_mut(), dt);
+            }
+        }
+
+        // 锚点重合
+        self.world_pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        self.world_pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.break_state.force += body_0_ins.apply_correction(self.compliance, self.world_pos1 - self.world_pos0, self.world_pos0, body_1_ins, self.world_pos1, dt).abs();
+        self.world_axis = body_0_ins.get_quat() * self.frame.axis0;
+    }
+
+    // 速度阶段: 读数为两个锚点的分离; 记录这一子步结束时的转角, 同一子步里多次迭代都以它为起点
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, mut body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        let pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        let pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.sensor = JointSensor::measure(body_0_ins, &body_1_ins, pos0, pos1, (pos1 - pos0).normalize_or_zero(), self.break_state.force);
+        if self.motor.is_some() {
+            self.prev_angle = Some(self.get_angle(body_0_ins, &mut body_1_ins));
+        }
+    }
+
+    pub fn get_sensor(&self) -> JointSensor {
+        self.sensor
+    }
+
+    // 最近一次求解时两个锚点和转轴的世界坐标
+    pub fn get_world_positions(&self) -> (Vec3, Vec3) {
+        (self.world_pos0, self.world_pos1)
+    }
+
+    pub fn get_world_axis(&self) -> Vec3 {
+        self.world_axis
+    }
+}
+
+impl Breakable for HingeJoint {
+    fn break_state(&self) -> &BreakState {
+        &self.break_state
+    }
+
+    fn break_state_mut(&mut self) -> &mut BreakState {
+        &mut self.break_state
+    }
+
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>) {
+        (self.body0, self.body1)
+    }
+}
@@ -0,0 +1,323 @@
+use crate::comp::arena::{Arena, Handle};
+use crate::comp::distance_joint::DistanceConstraint;
+use crate::comp::fixed_joint::FixedJoint;
+use crate::comp::hinge_joint::HingeJoint;
+use crate::comp::physics_world::PhysicsWorld;
+use crate::comp::prismatic_joint::PrismaticJoint;
+use crate::comp::rigidbody::{BodyHandle, RigidBody, Simulate};
+use crate::comp::spherical_joint::SphericalJoint;
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+
+// 关节共用的工具函数
+
+// 局部方向转到世界坐标, 没有刚体时 local_dir 本身就是世界坐标
+pub fn world_dir(body: &mut Option<&mut RigidBody>, local_dir: Vec3) -> Vec3 {
+    match body {
+        Some(ref mut body) => body.get_quat() * local_dir,
+        None => local_dir,
+    }
+}
+
+// 局部坐标点转到世界坐标, 没有刚体时 local_pos 本身就是世界坐标
+pub fn world_point(body: &Option<&mut RigidBody>, local_pos: Vec3) -> Vec3 {
+    match body {
+        Some(ref body) => body.local_to_world(local_pos),
+        None => local_pos,
+    }
+}
+
+// 关节在两个刚体上的局部坐标: 锚点, 轴和垂直于轴的参考方向, 以及创建时 body0 到 body1 的相对朝向
+// body1 为 None 时 1 侧为世界坐标
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JointFrame {
+    pub anchor0: Vec3,
+    pub anchor1: Vec3,
+    pub axis0: Vec3,
+    pub axis1: Vec3,
+    pub ref0: Vec3,
+    pub ref1: Vec3,
+    pub rest_rotation: Quat,
+}
+
+impl JointFrame {
+    // anchor 和 axis 为世界坐标, 按两个刚体当前的位姿换算到各自的局部坐标
+    pub fn new(physics: &PhysicsWorld, body0: BodyHandle, body1: Option<BodyHandle>, anchor: Vec3, axis: Vec3) -> Self {
+        let axis = axis.normalize();
+        let reference = axis.any_orthonormal_vector();
+        let (pos0, q0) = pose(physics.body(body0));
+        let (pos1, q1) = pose(body1.and_then(|body1| physics.body(body1)));
+        let inv_q0 = q0.inverse();
+        let inv_q1 = q1.inverse();
+        JointFrame {
+            anchor0: inv_q0 * (anchor - pos0),
+            anchor1: inv_q1 * (anchor - pos1),
+            axis0: inv_q0 * axis,
+            axis1: inv_q1 * axis,
+            ref0: inv_q0 * reference,
+            ref1: inv_q1 * reference,
+            rest_rotation: inv_q0 * q1,
+        }
+    }
+}
+
+// 刚体的位置和朝向, 没有刚体时为世界坐标系
+fn pose(body: Option<&RigidBody>) -> (Vec3, Quat) {
+    match body {
+        Some(body) => (body.get_position(), body.get_quat()),
+        None => (Vec3::ZERO, Quat::IDENTITY),
+    }
+}
+
+// n1 绕 n 转到 n2 的角度
+pub fn signed_angle(n: Vec3, n1: Vec3, n2: Vec3) -> f32 {
+    n1.cross(n2).dot(n).atan2(n1.dot(n2))
+}
+
+// 关节限制的上下限: NaN 的一侧视为不限, 上下颠倒时交换; 这是公开接口, 坏数据只在调试版本里报错, 不让进程中止
+pub fn sanitize_limits(name: &str, min: f32, max: f32) -> (f32, f32) {
+    debug_assert!(min <= max, "{name} ({min}, {max}) are inverted or NaN");
+    let min = if min.is_nan() { f32::NEG_INFINITY } else { min };
+    let max = if max.is_nan() { f32::INFINITY } else { max };
+    (min.min(max), min.max(max))
+}
+
+// 角度超出 [min, max] 时, 返回 body0 需要转过的旋转向量: 把 n1 转到限制角度处再与 n2 对齐
+// 要求 min <= max, 由 sanitize_limits 保证
+pub fn limit_angle(n: Vec3, n1: Vec3, n2: Vec3, min: f32, max: f32) -> Option<Vec3> {
+    let angle = signed_angle(n, n1, n2);
+    if angle >= min && angle <= max {
+        return None;
+    }
+    let limit_n1 = Quat::from_axis_angle(n, angle.clamp(min, max)) * n1;
+    Some(limit_n1.cross(n2))
+}
+
+// body0 需要转过的旋转向量, 使 body1 相对 body0 的朝向回到 rest_rotation; 连接到世界时 q1 取单位四元数
+pub fn rotation_error(q0: Quat, q1: Quat, rest_rotation: Quat) -> Vec3 {
+    let mut d_q = q1 * (q0 * rest_rotation).inverse();
+    if d_q.w < 0.0 {
+        d_q = -d_q;
+    }
+    2.0 * Vec3::new(d_q.x, d_q.y, d_q.z)
+}
+
+// 把角度换算到 (-π, π]
+pub fn wrap_angle(angle: f32) -> f32 {
+    let angle = angle.rem_euclid(TAU);
+    if angle > PI { angle - TAU } else { angle }
+}
+
+// 关节电机, 驱动铰链的转角或滑动副的位移
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JointMotor {
+    // 以目标速度转动或滑动: 每个子步把关节拉到上一子步的值加上 target * dt 处
+    Velocity { target: f32, max_force: f32 },
+    // 驱动到目标转角或位移, compliance 越大驱动越软
+    Position { target: f32, compliance: f32, max_force: f32 },
+}
+
+impl JointMotor {
+    // 速度电机换成目标速度 velocity, 位置电机换成目标值 position
+    pub fn with_targets(self, velocity: f32, position: f32) -> Self {
+        match self {
+            JointMotor::Velocity { max_force, .. } => JointMotor::Velocity { target: velocity, max_force },
+            JointMotor::Position { compliance, max_force, .. } => JointMotor::Position { target: position, compliance, max_force },
+        }
+    }
+
+    // 当前值 value 和上一子步的值 prev_value, 返回 (需要消除的误差, 柔度, 最大驱动力)
+    pub fn error(&self, value: f32, prev_value: f32, dt: f32) -> (f32, f32, f32) {
+        match *self {
+            JointMotor::Velocity { target, max_force } => (value - prev_value - target * dt, 0.0, max_force),
+            JointMotor::Position { target, compliance, max_force } => (value - target, compliance, max_force),
+        }
+    }
+}
+
+// 把误差限制在驱动力 max_force 以内: |λ| = |c| / (w + α), 力为 |λ| / dt², α = compliance / dt²
+pub fn clamp_motor_error(error: f32, w: f32, compliance: f32, max_force: f32, dt: f32) -> f32 {
+    let limit = max_force * (w * dt * dt + compliance);
+    error.clamp(-limit, limit)
+}
+
+// 关节的传感器读数, 每个子步结束时更新
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct JointSensor {
+    // 两个锚点之间的距离
+    pub length: f32,
+    // 约束力, 拉为正压为负
+    pub tension: f32,
+    // 沿连线方向的伸长速度, 拉长为正
+    pub axial_velocity: f32,
+}
+
+impl JointSensor {
+    // 按两个锚点的世界坐标量出沿 dir 方向的长度和伸长速度, 锚点重合的关节读数就是约束误差
+    pub fn measure(body_0: &RigidBody, body_1: &Option<&mut RigidBody>, pos0: Vec3, pos1: Vec3, dir: Vec3, tension: f32) -> Self {
+        let mut v = -body_0.get_point_velocity(pos0);
+        if let Some(ref body_1) = body_1 {
+            v += body_1.get_point_velocity(pos1);
+        }
+        JointSensor {
+            length: (pos1 - pos0).dot(dir),
+            tension,
+            axial_velocity: v.dot(dir),
+        }
+    }
+}
+
+// 关节被拉断时由 PhysicsWorld::step 返回, 关节已经从 PhysicsWorld 里删除
+#[derive(Clone, Copy, Debug)]
+pub struct JointBroken {
+    pub joint: JointHandle,
+    pub body0: BodyHandle,
+    pub body1: Option<BodyHandle>,
+    // 拉断时锚点处的约束力
+    pub force: f32,
+}
+
+// 关节断开的状态: 最近一个子步锚点处的约束力 (多次迭代时累加), 以及断开的阈值
+// 距离约束的约束力拉为正压为负, 其余关节只记大小
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BreakState {
+    pub force: f32,
+    pub break_force: Option<f32>,
+}
+
+impl BreakState {
+    // 约束力超过 break_force 后关节断开, 不再求解
+    pub fn is_broken(&self) -> bool {
+        match self.break_force {
+            Some(break_force) => self.force.abs() > break_force,
+            None => false,
+        }
+    }
+}
+
+// 可以被拉断的关节, 断开的逻辑都在 BreakState 里
+pub trait Breakable {
+    fn break_state(&self) -> &BreakState;
+    fn break_state_mut(&mut self) -> &mut BreakState;
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>);
+
+    fn set_break_force(&mut self, break_force: f32) {
+        self.break_state_mut().break_force = Some(break_force);
+    }
+
+    fn get_force(&self) -> f32 {
+        self.break_state().force.abs()
+    }
+
+    fn get_break_force(&self) -> Option<f32> {
+        self.break_state().break_force
+    }
+
+    fn reset_force(&mut self) {
+        self.break_state_mut().force = 0.0;
+    }
+
+    fn is_broken(&self) -> bool {
+        self.break_state().is_broken()
+    }
+}
+
+// PhysicsWorld 里的关节
+pub enum Joint {
+    Distance(DistanceConstraint),
+    Hinge(HingeJoint),
+    Spherical(SphericalJoint),
+    Prismatic(PrismaticJoint),
+    Fixed(FixedJoint),
+}
+
+// 关节在 PhysicsWorld 里的句柄
+pub type JointHandle = Handle<Joint>;
+
+impl Joint {
+    // 子步开始时清零约束力; 已经断开的关节保留断开时的力, 留给 PhysicsWorld 删除
+    pub fn begin_sub_step(&mut self) {
+        let joint: &mut dyn Breakable = match self {
+            Joint::Distance(joint) => joint,
+            Joint::Hinge(joint) => joint,
+            Joint::Spherical(joint) => joint,
+            Joint::Prismatic(joint) => joint,
+            Joint::Fixed(joint) => joint,
+        };
+        if !joint.is_broken() {
+            joint.reset_force();
+        }
+    }
+
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        match self {
+            Joint::Distance(joint) => joint.solve(bodies, dt),
+            Joint::Hinge(joint) => joint.solve(bodies, dt),
+            Joint::Spherical(joint) => joint.solve(bodies, dt),
+            Joint::Prismatic(joint) => joint.solve(bodies, dt),
+            Joint::Fixed(joint) => joint.solve(bodies, dt),
+        }
+    }
+
+    // update_vel 之后的速度阶段: 更新传感器读数, 距离约束的阻尼, 电机记录这一子步结束时的值
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        match self {
+            Joint::Distance(joint) => joint.solve_velocity(bodies, dt),
+            Joint::Hinge(joint) => joint.solve_velocity(bodies),
+            Joint::Spherical(joint) => joint.solve_velocity(bodies),
+            Joint::Prismatic(joint) => joint.solve_velocity(bodies),
+            Joint::Fixed(joint) => joint.solve_velocity(bodies),
+        }
+    }
+
+    pub fn get_sensor(&self) -> JointSensor {
+        match self {
+            Joint::Distance(joint) => joint.get_sensor(),
+            Joint::Hinge(joint) => joint.get_sensor(),
+            Joint::Spherical(joint) => joint.get_sensor(),
+            Joint::Prismatic(joint) => joint.get_sensor(),
+            Joint::Fixed(joint) => joint.get_sensor(),
+        }
+    }
+
+    pub fn as_breakable(&self) -> &dyn Breakable {
+        match self {
+            Joint::Distance(joint) => joint,
+            Joint::Hinge(joint) => joint,
+            Joint::Spherical(joint) => joint,
+            Joint::Prismatic(joint) => joint,
+            Joint::Fixed(joint) => joint,
+        }
+    }
+}
+
+impl From<DistanceConstraint> for Joint {
+    fn from(joint: DistanceConstraint) -> Self {
+        Joint::Distance(joint)
+    }
+}
+
+impl From<HingeJoint> for Joint {
+    fn from(joint: HingeJoint) -> Self {
+        Joint::Hinge(joint)
+    }
+}
+
+impl From<SphericalJoint> for Joint {
+    fn from(joint: SphericalJoint) -> Self {
+        Joint::Spherical(joint)
+    }
+}
+
+impl From<PrismaticJoint> for Joint {
+    fn from(joint: PrismaticJoint) -> Self {
+        Joint::Prismatic(joint)
+    }
+}
+
+impl From<FixedJoint> for Joint {
+    fn from(joint: FixedJoint) -> Self {
+        Joint::Fixed(joint)
+    }
+}
@@ -1,4 +1,27 @@
+pub mod arena;
+
 pub mod rigidbody;
 
 pub mod distance_joint;
 
+pub mod joint;
+
+pub mod hinge_joint;
+
+pub mod spherical_joint;
+
+pub mod prismatic_joint;
+
+pub mod fixed_joint;
+
+pub mod contact;
+
+pub mod collision;
+
+pub mod broad_phase;
+
+pub mod shape;
+
+pub mod convex_hull;
+
+pub mod physics_world;
@@ -0,0 +1,252 @@
+use crate::comp::arena::Arena;
+use crate::comp::broad_phase::BroadPhase;
+use crate::comp::contact::{collect_contacts, Contact, GroundPlane};
+use crate::comp::joint::{Joint, JointBroken, JointHandle};
+use crate::comp::rigidbody::{BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+
+pub const GRAVITY: Vec3 = Vec3::new(0.0, -9.80, 0.0);
+
+// 驱动整个求解流程的参数, 运行时可以修改
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsConfig {
+    // 每次 step 前进的时间, 单位秒
+    pub timestep: f32,
+    pub num_sub_steps: usize,
+    // 每个子步里位置约束 (关节和接触) 的迭代次数
+    pub num_iterations: usize,
+    pub gravity: Vec3,
+    // 风和重力一样作为加速度施加在所有刚体上
+    pub wind: Vec3,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            timestep: 1.0 / 64.0,
+            num_sub_steps: 8,
+            num_iterations: 1,
+            gravity: GRAVITY,
+            wind: Vec3::ZERO,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    pub fn sub_step_dt(&self) -> f32 {
+        self.timestep / self.num_sub_steps.max(1) as f32
+    }
+
+    pub fn acceleration(&self) -> Vec3 {
+        self.gravity + self.wind
+    }
+}
+
+// 不依赖 Bevy ECS 的物理世界: 刚体和关节放在 arena 里, 用句柄互相引用
+pub struct PhysicsWorld {
+    bodies: Arena<RigidBody>,
+    joints: Arena<Joint>,
+    broad_phase: BroadPhase,
+    contacts: Vec<Contact>,
+    pub ground: GroundPlane,
+    pub config: PhysicsConfig,
+    // 从创建或 clear 起模拟过的时间, 休眠按它计时
+    time: f32,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        PhysicsWorld {
+            bodies: Arena::new(),
+            joints: Arena::new(),
+            broad_phase: BroadPhase::default(),
+            contacts: Vec::new(),
+            ground: GroundPlane::default(),
+            config: PhysicsConfig::default(),
+            time: 0.0,
+        }
+    }
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: PhysicsConfig) -> Self {
+        PhysicsWorld { config, ..Self::default() }
+    }
+
+    pub fn add_body(&mut self, body: RigidBody) -> BodyHandle {
+        self.bodies.insert(body)
+    }
+
+    // 连在这个刚体上的关节留在世界里, 但不再求解
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<RigidBody> {
+        self.bodies.remove(handle)
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        self.bodies.get(handle)
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        self.bodies.get_mut(handle)
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.bodies.iter()
+    }
+
+    pub fn bodies_mut(&mut self) -> impl Iterator<Item = (BodyHandle, &mut RigidBody)> {
+        self.bodies.iter_mut()
+    }
+
+    // 关节按加入的顺序求解
+    pub fn add_joint(&mut self, joint: impl Into<Joint>) -> JointHandle {
+        self.joints.insert(joint.into())
+    }
+
+    pub fn remove_joint(&mut self, handle: JointHandle) -> Option<Joint> {
+        self.joints.remove(handle)
+    }
+
+    pub fn joint(&self, handle: JointHandle) -> Option<&Joint> {
+        self.joints.get(handle)
+    }
+
+    pub fn joint_mut(&mut self, handle: JointHandle) -> Option<&mut Joint> {
+        self.joints.get_mut(handle)
+    }
+
+    pub fn joints(&self) -> impl Iterator<Item = (JointHandle, &Joint)> {
+        self.joints.iter()
+    }
+
+    pub fn joints_mut(&mut self) -> impl Iterator<Item = (JointHandle, &mut Joint)> {
+        self.joints.iter_mut()
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    // 删除所有刚体和关节, 模拟时间归零, 地面和 config 保持不变; 之前的句柄全部失效
+    pub fn clear(&mut self) {
+        self.time = 0.0;
+        self.bodies.clear();
+        self.joints.clear();
+        self.contacts.clear();
+        self.broad_phase = BroadPhase::default();
+    }
+
+    // 前进 config.timestep 秒, 返回这一步里被拉断的关节, 它们已经从世界里删除
+    pub fn step(&mut self) -> Vec<JointBroken> {
+        let s_dt = self.config.sub_step_dt();
+        let g = self.config.acceleration();
+        for _i in 0..self.config.num_sub_steps {
+            for (_, body) in self.bodies.iter_mut() {
+                body.step(s_dt, g);
+            }
+
+            self.broad_phase.update(&self.bodies);
+            collect_contacts(&self.bodies, self.broad_phase.pairs(), &mut self.contacts);
+            self.ground.collect_contacts(&self.bodies, &mut self.contacts);
+
+            for (_, joint) in self.joints.iter_mut() {
+                joint.begin_sub_step();
+            }
+
+            for _j in 0..self.config.num_iterations.max(1) {
+                for (_, joint) in self.joints.iter_mut() {
+                    joint.solve(&mut self.bodies, s_dt);
+                }
+
+                for contact in self.contacts.iter_mut() {
+                    contact.solve(&mut self.bodies, s_dt);
+                }
+            }
+
+            for contact in self.contacts.iter_mut() {
+                contact.solve_friction(&mut self.bodies, s_dt);
+            }
+
+            for (_, body) in self.bodies.iter_mut() {
+                body.update_vel(s_dt);
+            }
+
+            for (_, joint) in self.joints.iter_mut() {
+                joint.solve_velocity(&mut self.bodies, s_dt);
+            }
+
+            for contact in self.contacts.iter_mut() {
+                contact.solve_velocity(&mut self.bodies, s_dt);
+            }
+
+            // 静止接触的阈值只看重力, 风再大也不应压掉弹跳
+            let gravity = self.config.gravity.length();
+            for contact in self.contacts.iter_mut() {
+                contact.solve_restitution(&mut self.bodies, s_dt, gravity);
+            }
+        }
+        self.time += self.config.timestep;
+        for (_, body) in self.bodies.iter_mut() {
+            body.ready_sleep(self.time);
+        }
+        self.remove_broken_joints()
+    }
+
+    fn remove_broken_joints(&mut self) -> Vec<JointBroken> {
+        let broken: Vec<JointBroken> = self.joints.iter()
+            .map(|(handle, joint)| (handle, joint.as_breakable()))
+            .filter(|(_, joint)| joint.is_broken())
+            .map(|(handle, joint)| {
+                let (body0, body1) = joint.get_body_handles();
+                JointBroken { joint: handle, body0, body1, force: joint.get_force() }
+            })
+            .collect();
+        for joint_broken in broken.iter() {
+            self.joints.remove(joint_broken.joint);
+        }
+        broken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::shape::Shape;
+    use glam::vec3;
+
+    // 0.2 的方块从 0.5 高处落到地面, 落稳后休眠计时到期, 变成静止刚体
+    #[test]
+    fn box_comes_to_rest_on_ground_and_sleeps() {
+        let mut physics = PhysicsWorld::new();
+        let mut body = RigidBody::new(vec3(0.0, 0.5, 0.0), Shape::Cuboid { size: Vec3::splat(0.2) }, Vec3::ZERO, 20.0);
+        body.set_sleep(true);
+        body.sleep_time = 1.0;
+        let handle = physics.add_body(body);
+
+        for _ in 0..64 {
+            physics.step();
+        }
+        let body = physics.body(handle).unwrap();
+        assert!(!body.is_static());
+        assert!((body.get_position().y - 0.1).abs() < 2e-3, "{}", body.get_position());
+        assert!(body.get_velocity().length() < 1e-2, "{}", body.get_velocity());
+        // 平放着落地, 不会翻倒
+        assert!((body.get_quat() * Vec3::Y).angle_between(Vec3::Y) < 1e-3, "{}", body.get_quat());
+
+        // 计时从 1 秒开始, 0.35 秒后睡下, 之后位置不再变化
+        for _ in 0..32 {
+            physics.step();
+        }
+        let rest = physics.body(handle).unwrap().get_position();
+        assert!(physics.body(handle).unwrap().is_static());
+        for _ in 0..64 {
+            physics.step();
+        }
+        assert_eq!(physics.body(handle).unwrap().get_position(), rest);
+        assert!((rest.y - 0.1).abs() < 2e-3);
+    }
+}
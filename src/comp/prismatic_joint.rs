@@ -0,0 +1,169 @@
+use crate::comp::arena::Arena;
+use crate::comp::joint::{clamp_motor_error, rotation_error, sanitize_limits, world_point, Breakable, BreakState, JointFrame, JointMotor, JointSensor};
+use crate::comp::physics_world::PhysicsWorld;
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::{Quat, Vec3};
+
+// 滑动副: 锁定相对转动, 两个锚点只能沿 body0 上的滑动轴相对移动, body1 为 None 时连接到世界
+pub struct PrismaticJoint {
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
+    // 锚点和滑动轴的局部坐标, 以及创建时 body0 到 body1 的相对朝向
+    frame: JointFrame,
+    compliance: f32,
+    // 锚点沿滑动轴的距离限制 (min, max), 创建时为 0
+    limits: Option<(f32, f32)>,
+    motor: Option<JointMotor>,
+    // 上一子步结束时沿滑动轴的距离, 速度电机用
+    prev_distance: Option<f32>,
+    break_state: BreakState,
+    // 最近一个子步结束时的读数
+    sensor: JointSensor,
+    world_pos0: Vec3,
+    world_pos1: Vec3,
+    world_axis: Vec3,
+}
+
+impl PrismaticJoint {
+    // anchor 和 axis 为世界坐标, 按两个刚体当前的位姿换算到局部坐标
+    pub fn new(
+        physics: &PhysicsWorld,
+        body0: BodyHandle,
+        body1: Option<BodyHandle>,
+        anchor: Vec3,
+        axis: Vec3,
+        compliance: f32,
+    ) -> Self {
+        Self {
+            body0,
+            body1,
+            frame: JointFrame::new(physics, body0, body1, anchor, axis),
+            compliance,
+            limits: None,
+            motor: None,
+            prev_distance: None,
+            break_state: BreakState::default(),
+            sensor: JointSensor::default(),
+            world_pos0: anchor,
+            world_pos1: anchor,
+            world_axis: axis.normalize(),
+        }
+    }
+
+    // 从快照恢复时换成保存下来的局部坐标, 保留创建时的参考方向和相对朝向
+    pub fn set_frame(&mut self, frame: JointFrame) {
+        self.frame = frame;
+    }
+
+    pub fn get_frame(&self) -> JointFrame {
+        self.frame
+    }
+
+    pub fn get_compliance(&self) -> f32 {
+        self.compliance
+    }
+
+    pub fn set_limits(&mut self, min: f32, max: f32) {
+        self.limits = Some(sanitize_limits("prismatic limits", min, max));
+    }
+
+    // 电机驱动锚点沿滑动轴的距离, 速度单位为米每秒
+    pub fn set_motor(&mut self, motor: JointMotor) {
+        self.motor = Some(motor);
+    }
+
+    pub fn get_motor(&self) -> Option<JointMotor> {
+        self.motor
+    }
+
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, mut body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        // 锁定相对转动: body1 的朝向与 body0 推算出的目标朝向之差
+        let q1 = match body_1_ins {
+            Some(ref mut body_1) => body_1.get_quat(),
+            None => Quat::IDENTITY,
+        };
+        let corr = rotation_error(body_0_ins.get_quat(), q1, self.frame.rest_rotation);
+        body_0_ins.apply_rotation_correction(self.compliance, corr, body_1_ins.as_deref_mut(), dt);
+
+        // 电机: 沿滑动轴移动 body0 以减小距离误差, 驱动力不超过 max_force
+        if let Some(motor) = self.motor {
+            self.world_axis = body_0_ins.get_quat() * self.frame.axis0;
+            self.world_pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+            self.world_pos1 = world_point(&body_1_ins, self.frame.anchor1);
+            let distance = self.world_axis.dot(self.world_pos1 - self.world_pos0);
+            let (error, compliance, max_force) = motor.error(distance, self.prev_distance.unwrap_or(distance), dt);
+            let mut w = body_0_ins.get_mass_inv(self.world_axis, self.world_pos0);
+            if let Some(ref body_1) = body_1_ins {
+                w += body_1.get_mass_inv(self.world_axis, self.world_pos1);
+            }
+            let error = clamp_motor_error(error, w, compliance, max_force, dt);
+            body_0_ins.apply_correction(compliance, self.world_axis * error, self.world_pos0, body_1_ins.as_deref_mut(), self.world_pos1, dt);
+        }
+
+        // 垂直于滑动轴的偏移全部消除, 沿轴的距离超出限制的部分也消除
+        self.world_axis = body_0_ins.get_quat() * self.frame.axis0;
+        self.world_pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        self.world_pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        let delta = self.world_pos1 - self.world_pos0;
+        let distance = self.world_axis.dot(delta);
+        let mut corr = delta - self.world_axis * distance;
+        if let Some((min, max)) = self.limits {
+            corr += self.world_axis * (distance - distance.clamp(min, max));
+        }
+        self.break_state.force += body_0_ins.apply_correction(self.compliance, corr, self.world_pos0, body_1_ins, self.world_pos1, dt).abs();
+    }
+
+    // 速度阶段: 读数为沿滑动轴的距离和速度, 距离同时记下给电机用, 同一子步里多次迭代都以它为起点
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        let axis = body_0_ins.get_quat() * self.frame.axis0;
+        let pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        let pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.sensor = JointSensor::measure(body_0_ins, &body_1_ins, pos0, pos1, axis, self.break_state.force);
+        if self.motor.is_some() {
+            self.prev_distance = Some(self.sensor.length);
+        }
+    }
+
+    pub fn get_sensor(&self) -> JointSensor {
+        self.sensor
+    }
+
+    // 最近一次求解时两个锚点和滑动轴的世界坐标
+    pub fn get_world_positions(&self) -> (Vec3, Vec3) {
+        (self.world_pos0, self.world_pos1)
+    }
+
+    pub fn get_world_axis(&self) -> Vec3 {
+        self.world_axis
+    }
+
+    pub fn get_limits(&self) -> Option<(f32, f32)> {
+        self.limits
+    }
+}
+
+impl Breakable for PrismaticJoint {
+    fn break_state(&self) -> &BreakState {
+        &self.break_state
+    }
+
+    fn break_state_mut(&mut self) -> &mut BreakState {
+        &mut self.break_state
+    }
+
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>) {
+        (self.body0, self.body1)
+    }
+}
@@ -1,7 +1,7 @@
-use bevy::math::{vec3, EulerRot, Mat3, Quat, Vec3};
-use bevy::prelude::{Component, Mut};
-use std::ops::{Mul, Neg};
-use bevy::render::render_resource::ShaderType;
+use crate::comp::arena::{Arena, Handle};
+use crate::comp::shape::{ConvexPart, Shape};
+use glam::{vec3, EulerRot, Mat3, Quat, Vec3};
+use std::ops::Neg;
 
 pub trait Simulate {
     fn step(&mut self, dt: f32, acceleration: Vec3);
@@ -10,155 +10,212 @@ pub trait Simulate {
 
     fn _apply_correction(&mut self, corr:Vec3, pos: Vec3);
 
-    fn apply_correction(&mut self, compliance: f32, corr:Vec3, pos: Vec3, other_body:  Option<Mut<CuboidBody>>,other_pos: Vec3,)->f32;
+    // 子步长 dt 用来换算柔度 compliance, 返回 λ / dt²
+    fn apply_correction(&mut self, compliance: f32, corr:Vec3, pos: Vec3, other_body:  Option<&mut RigidBody>,other_pos: Vec3, dt: f32)->f32;
 
-    fn get_position(&mut self) -> Vec3;
+    fn _apply_velocity_correction(&mut self, impulse: Vec3, pos: Vec3);
+
+    fn apply_velocity_correction(&mut self, corr: Vec3, pos: Vec3, other_body: Option<&mut RigidBody>, other_pos: Vec3);
+
+    fn _apply_rotation_correction(&mut self, corr: Vec3);
+
+    fn apply_rotation_correction(&mut self, compliance: f32, corr: Vec3, other_body: Option<&mut RigidBody>, dt: f32) -> f32;
+
+    fn get_position(&self) -> Vec3;
     fn get_velocity(&self) -> Vec3;
 
-    fn get_quat(&mut self) -> Quat;
+    fn get_quat(&self) -> Quat;
 
 
     fn get_angule_vel(&self) -> Vec3;
 
+    fn get_point_velocity(&self, pos: Vec3) -> Vec3;
+
     fn  get_mass(&self) -> f32;
     fn  get_mass_inv(&self, normal: Vec3, pos: Vec3) -> f32;
+    fn  get_angular_mass_inv(&self, normal: Vec3) -> f32;
 
 
     fn local_to_world(&self, local_pos: Vec3) -> Vec3;
 
     fn world_to_local(&self, world_pos: Vec3) -> Vec3;
 
+    fn prev_local_to_world(&self, local_pos: Vec3) -> Vec3;
+
 
-    fn get_inv_inertia(&self) -> Vec3;
+    fn get_inv_inertia(&self) -> Mat3;
 
     fn get_inertia(&self) -> Mat3;
 
     fn get_centroid_world_pos(&self) -> Vec3;
 }
 
-#[derive(Component)]
+
+// 刚体在 PhysicsWorld 里的句柄
+pub type BodyHandle = Handle<RigidBody>;
+
 pub struct RigidBody {
     position: Vec3,
     velocity: Vec3,
     prev_pos: Vec3,
     angular_velocity: Vec3,
+    // 世界坐标下的外力矩, 每个子步都作用
+    torque: Vec3,
     mass: f32,
     b_static: bool,
     b_sleep: bool,
-    //restitution: f32,
+    restitution: f32,
+    static_friction: f32,
+    dynamic_friction: f32,
     quaternion: Quat,
     prev_quaternion: Quat,
     inv_quaternion: Quat,
     centroid: Vec3,
+    // 局部坐标下绕质心的转动惯量张量及其逆
     inertia: Mat3,
     inv_mass: f32,
-    inv_inertia: Vec3,
+    inv_inertia: Mat3,
     density:f32,
     damping:f32,
-    dt: f32,
+    shape: Shape,
+    // 以质心为原点的局部坐标下的碰撞凸块, 由 shape 生成后缓存
+    parts: Vec<ConvexPart>,
+    pub sleep_time:f32,
 }
 impl RigidBody {
-    fn new(pos:Vec3, angels: Vec3, density: f32) -> RigidBody {
-        let rigid_body =  RigidBody{
+    // pos 为形状坐标系原点的世界坐标; 组合体的质心不在原点, position 取质心
+    pub fn new(pos:Vec3, shape: Shape, angels: Vec3, density: f32) -> RigidBody {
+        let centroid = shape.centroid();
+        let parts = shape.parts().into_iter()
+            .map(|part| part.transformed(-centroid, Quat::IDENTITY))
+            .collect();
+        let pos = pos + Quat::from_euler(EulerRot::XYZ, angels.x, angels.y, angels.z) * centroid;
+        let mut rigid_body =  RigidBody{
             position: pos,
             velocity: Vec3::new(0.0, 0.0, 0.0),
             prev_pos: pos,
             angular_velocity: Default::default(),
+            torque: Vec3::ZERO,
             inv_mass: 0.0,
             mass: 1.0,
             b_static: false,
             b_sleep: false,
+            restitution: 0.0,
+            static_friction: 0.5,
+            dynamic_friction: 0.3,
             quaternion: Quat::from_euler(EulerRot::XYZ,angels.x, angels.y, angels.z),
             prev_quaternion: Quat::from_euler(EulerRot::XYZ,angels.x, angels.y, angels.z),
             inv_quaternion: Quat::from_euler(EulerRot::XYZ, angels.x, angels.y, angels.z).inverse(),
-            centroid: Vec3::ZERO,
+            centroid,
             inertia: Mat3::IDENTITY,
             density,
-            inv_inertia: Vec3::ONE,
+            inv_inertia: Mat3::IDENTITY,
             damping: 0.05,
-            dt: 0.03,
+            parts,
+            shape,
+            sleep_time: 0.0,
         };
+        rigid_body.set_mass(1.0);
         rigid_body
     }
 
-    fn init(&mut self, mass: f32, inv_mass: f32, inv_inertia: Vec3) {
+    fn init(&mut self, mass: f32, inv_mass: f32, inv_inertia: Mat3) {
         self.mass = mass;
         self.inv_mass = inv_mass;
         self.inv_inertia = inv_inertia;
     }
-}
-
-#[derive(Component)]
-pub struct CuboidBody {
-    rigid_body: RigidBody,
-    x_size: f32,
-    y_size: f32,
-    z_size: f32,
-    vertices: Vec<Vec3>,
-    pub sleep_time:f32,
-    pub base_density: f32,
-}
-
-impl CuboidBody {
-    pub fn new(pos:Vec3, size: Vec3, angles:Vec3, density: f32,) -> CuboidBody {
-         let mut cuboid_body = CuboidBody{
-            rigid_body: RigidBody::new(pos, angles, density),
-            x_size: size.x,
-            y_size: size.y,
-            z_size : size.z,
-            vertices: vec![],
-            sleep_time: 0.0,
-             base_density: density,
-         };
-        let mass = density * size.x * size.y * size.z;
-        let inv_mass = 1.0 / mass;
-        let ix = 1.0 / 12.0 * mass * (size.y * size.y + size.z * size.z);
-        let iy = 1.0 / 12.0 * mass * (size.x * size.x + size.z * size.z);
-        let iz = 1.0 / 12.0 * mass * (size.x * size.x + size.y * size.y);
-        let inv_inertia =  Vec3::new(1.0 / ix, 1.0 / iy, 1.0 / iz);
-        cuboid_body.rigid_body.init(mass, inv_mass, inv_inertia);
-        let ex = 0.5 * size.x;
-        let ey = 0.5 * size.y;
-        let ez = 0.5 * size.z;
-
-        cuboid_body.vertices.push(vec3(-ex, -ey, -ez));
-        cuboid_body.vertices.push(vec3(ex, -ey, -ez));
-        cuboid_body.vertices.push(vec3(ex, ey, -ez));
-        cuboid_body.vertices.push(vec3(-ex, ey, -ez));
-        cuboid_body.vertices.push(vec3(-ex, -ey, ez));
-        cuboid_body.vertices.push(vec3(ex, -ey, ez));
-        cuboid_body.vertices.push(vec3(ex, ey, ez));
-        cuboid_body.vertices.push(vec3(-ex, ey, ez));
-
-        cuboid_body
-    }
 
     pub fn set_sleep(&mut self, b_sleep: bool){
-        self.rigid_body.b_sleep = b_sleep;
+        self.b_sleep = b_sleep;
     }
 
     pub fn ready_sleep(&mut self, curr_time: f32)->bool{
-        if self.rigid_body.b_sleep == true{
-            if curr_time - self.sleep_time > 0.35{
-                self.rigid_body.b_static = true;
-                return true
-            }
+        if self.b_sleep && curr_time - self.sleep_time > 0.35 {
+            self.b_static = true;
+            return true
         }
         false
     }
+    pub fn is_static(&self) -> bool {
+        self.b_static
+    }
+
+    // 直接固定不动, 不经过休眠
+    pub fn set_static(&mut self, b_static: bool) {
+        self.b_static = b_static;
+    }
+
+    pub fn is_sleep(&self) -> bool {
+        self.b_sleep
+    }
+
+    pub fn set_friction(&mut self, static_friction: f32, dynamic_friction: f32) {
+        self.static_friction = static_friction;
+        self.dynamic_friction = dynamic_friction;
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec3) {
+        self.velocity = velocity;
+    }
+
+    pub fn set_angular_velocity(&mut self, angular_velocity: Vec3) {
+        self.angular_velocity = angular_velocity;
+    }
+
+    pub fn set_torque(&mut self, torque: Vec3) {
+        self.torque = torque;
+    }
+
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.restitution = restitution;
+    }
+
+    // 创建时的密度, 不含 set_mass 的质量系数
+    pub fn get_density(&self) -> f32 {
+        self.density
+    }
+
+    pub fn get_restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    pub fn get_static_friction(&self) -> f32 {
+        self.static_friction
+    }
+
+    pub fn get_dynamic_friction(&self) -> f32 {
+        self.dynamic_friction
+    }
+
+    pub fn get_shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn get_parts(&self) -> &Vec<ConvexPart> {
+        &self.parts
+    }
+
+    // 质心在形状坐标系下的位置
+    pub fn get_centroid(&self) -> Vec3 {
+        self.centroid
+    }
+
     pub fn set_mass(&mut self, mass_factor: f32){
-        let new_mass = mass_factor *  self.base_density * self.x_size * self.y_size * self.z_size;
+        let (new_mass, inertia) = self.shape.mass_properties(mass_factor * self.density);
+        // 空组合体等体积为 0 的形状或非正的质量系数得不到有效质量, 保留原来的质量;
+        // 新建的刚体此时 inv_mass 仍为 0, 不参与运动, 避免 1 / 0 把 NaN 传开
+        if !(new_mass > 0.0 && new_mass.is_finite()) {
+            return;
+        }
         let new_inv_mass = 1.0 / new_mass;
-        let ix = 1.0 / 12.0 * new_mass * ( self.y_size*  self.y_size +  self.z_size *  self.z_size);
-        let iy = 1.0 / 12.0 * new_mass * ( self.x_size *  self.x_size +  self.z_size *  self.z_size);
-        let iz = 1.0 / 12.0 * new_mass * ( self.x_size *  self.x_size+  self.y_size *  self.y_size);
-        let new_inv_inertia =  Vec3::new(1.0 / ix, 1.0 / iy, 1.0 / iz);
-        self.rigid_body.init(new_mass, new_inv_mass, new_inv_inertia);
+        // 凸包和组合体的惯量张量一般不是对角阵, 直接取逆
+        let new_inv_inertia = if inertia.determinant() > 0.0 { inertia.inverse() } else { Mat3::ZERO };
+        self.inertia = inertia;
+        self.init(new_mass, new_inv_mass, new_inv_inertia);
     }
 }
 impl Simulate for RigidBody{
     fn step(&mut self, dt: f32, acceleration: Vec3){
-        self.dt = dt;
         if self.inv_mass == 0.0 {return;}
         if self.b_static {return;}
         //self.prev_vel = self.velocity;
@@ -166,6 +223,11 @@ impl Simulate for RigidBody{
         self.velocity += acceleration * dt;
         self.position += self.velocity * dt;
 
+        // 局部坐标下的欧拉方程 I dω/dt = τ - ω × Iω, 非球对称的物体因此会进动和翻转
+        let w = self.inv_quaternion * self.angular_velocity;
+        let tau = self.inv_quaternion * self.torque;
+        let d_w = self.inv_inertia * (tau - w.cross(self.inertia * w));
+        self.angular_velocity += self.quaternion * (d_w * dt);
 
         self.prev_quaternion = self.quaternion;
         let mut d_rot = Quat::from_xyzw(self.angular_velocity.x,
@@ -208,22 +270,102 @@ impl Simulate for RigidBody{
 
         self.position += corr * self.inv_mass;
 
+        let d_w = pos - self.position;
+
+        self._apply_rotation_correction(d_w.cross(corr));
+    }
+
+    fn apply_correction(&mut self, compliance: f32, corr: Vec3, pos: Vec3, other_body:  Option<&mut RigidBody>, other_pos: Vec3, dt: f32) -> f32 {
+        if corr.length() == 0.0 {return 0.0;}
+
+        let c = corr.length();
+
+        let mut normal = corr.normalize();
+
+        let mut w = self.get_mass_inv(normal, pos);
+        if let Some(ref other_body1) = other_body{
+            w += other_body1.get_mass_inv(normal, other_pos);
+        }
+
+
+        if w == 0.0 {
+            return 0.0;
+        }
+
+        let alpha = compliance / dt / dt;
+
+        let lambda = -c / (w + alpha);
+
+        normal *= -lambda;
+
+        self._apply_correction(normal, pos);
+
+        if let Some(other_body_ref) = other_body{
+            normal *= -1.0;
+            other_body_ref._apply_correction(normal, other_pos);
+        }
+
+        lambda / dt / dt
+
+    }
+
+    fn _apply_velocity_correction(&mut self, impulse: Vec3, pos: Vec3) {
+        if self.inv_mass == 0.0 {return;}
+        if self.b_static {return;}
+
+        self.velocity += impulse * self.inv_mass;
+
         let mut d_w = pos - self.position;
 
-        d_w = d_w.cross(corr);
+        d_w = d_w.cross(impulse);
 
         d_w = self.inv_quaternion.mul_vec3(d_w);
 
-        d_w = d_w.mul(self.inv_inertia);
+        d_w = self.inv_inertia * d_w;
 
         d_w = self.quaternion.mul_vec3(d_w);
 
+        self.angular_velocity += d_w;
+    }
+
+    fn apply_velocity_correction(&mut self, corr: Vec3, pos: Vec3, other_body: Option<&mut RigidBody>, other_pos: Vec3) {
+        if corr.length() == 0.0 {return;}
+
+        let normal = corr.normalize();
+
+        let mut w = self.get_mass_inv(normal, pos);
+        if let Some(ref other_body1) = other_body{
+            w += other_body1.get_mass_inv(normal, other_pos);
+        }
+
+        if w == 0.0 {
+            return;
+        }
+
+        let impulse = corr / w;
+
+        self._apply_velocity_correction(impulse, pos);
+
+        if let Some(other_body_ref) = other_body{
+            other_body_ref._apply_velocity_correction(-impulse, other_pos);
+        }
+    }
+
+    // corr 为世界坐标下的角冲量, 只改变朝向不改变位置
+    fn _apply_rotation_correction(&mut self, corr: Vec3) {
+        if self.inv_mass == 0.0 {return;}
+        if self.b_static {return;}
+
+        let mut d_w = self.inv_quaternion.mul_vec3(corr);
+
+        d_w = self.inv_inertia * d_w;
+
+        d_w = self.quaternion.mul_vec3(d_w);
 
         let mut d_rot = Quat::from_xyzw(d_w.x, d_w.y, d_w.z, 0.0);
 
         d_rot = d_rot.mul_quat(self.quaternion);
 
-
         self.quaternion.x += 0.5 * d_rot.x;
         self.quaternion.y += 0.5 * d_rot.y;
         self.quaternion.z += 0.5 * d_rot.z;
@@ -232,42 +374,41 @@ impl Simulate for RigidBody{
         self.inv_quaternion = self.quaternion.inverse();
     }
 
-    fn apply_correction(&mut self, compliance: f32, corr: Vec3, pos: Vec3, other_body:  Option<Mut<CuboidBody>>, other_pos: Vec3,) -> f32 {
+    // 只改变朝向的修正, corr 为世界坐标下 body0 需要转过的旋转向量, 返回约束力矩
+    fn apply_rotation_correction(&mut self, compliance: f32, corr: Vec3, other_body: Option<&mut RigidBody>, dt: f32) -> f32 {
         if corr.length() == 0.0 {return 0.0;}
 
         let c = corr.length();
 
         let mut normal = corr.normalize();
 
-        let mut w = self.get_mass_inv(normal, pos);
+        let mut w = self.get_angular_mass_inv(normal);
         if let Some(ref other_body1) = other_body{
-            w += other_body1.get_mass_inv(normal, other_pos);
+            w += other_body1.get_angular_mass_inv(normal);
         }
 
-
         if w == 0.0 {
             return 0.0;
         }
 
-        let alpha = compliance / self.dt / self.dt;
+        let alpha = compliance / dt / dt;
 
         let lambda = -c / (w + alpha);
 
         normal *= -lambda;
 
-        self._apply_correction(normal, pos);
+        self._apply_rotation_correction(normal);
 
-        if let Some(mut other_body_ref) = other_body{
+        if let Some(other_body_ref) = other_body{
             normal *= -1.0;
-            other_body_ref._apply_correction(normal, other_pos);
+            other_body_ref._apply_rotation_correction(normal);
         }
 
-        lambda / self.dt / self.dt
-
+        lambda / dt / dt
     }
 
 
-    fn get_position(&mut self) -> Vec3 {
+    fn get_position(&self) -> Vec3 {
         self.position
     }
 
@@ -275,7 +416,7 @@ impl Simulate for RigidBody{
         self.velocity
     }
 
-    fn get_quat(&mut self) -> Quat {
+    fn get_quat(&self) -> Quat {
         self.quaternion
     }
 
@@ -283,38 +424,52 @@ impl Simulate for RigidBody{
         self.angular_velocity
     }
 
+    fn get_point_velocity(&self, pos: Vec3) -> Vec3 {
+        self.velocity + self.angular_velocity.cross(pos - self.position)
+    }
+
     fn get_mass(&self) -> f32 {
         self.mass
     }
 
     fn get_mass_inv(&self, normal: Vec3, pos: Vec3) -> f32 {
         if self.inv_mass == 0.0 {return 0.0;}
+        if self.b_static {return 0.0;}
 
         let mut rn = pos -self.position;
         rn = rn.cross(normal);
         rn = self.inv_quaternion.mul_vec3(rn);
 
-        let mut w = rn.x * rn.x * self.inv_inertia.x +
-            rn.y * rn.y  * self.inv_inertia.y +
-            rn.z * rn.z * self.inv_inertia.z;
+        let mut w = rn.dot(self.inv_inertia * rn);
 
         w += self.inv_mass;
         w
     }
 
+    // 绕世界坐标下的单位轴 normal 转动时的广义逆质量 nᵀ I⁻¹ n
+    fn get_angular_mass_inv(&self, normal: Vec3) -> f32 {
+        if self.inv_mass == 0.0 {return 0.0;}
+        if self.b_static {return 0.0;}
+
+        let n = self.inv_quaternion.mul_vec3(normal);
+        n.dot(self.inv_inertia * n)
+    }
+
 
 
     fn local_to_world(&self, local_pos: Vec3) -> Vec3 {
-        let world_pos =  self.position +  self.quaternion * local_pos;
-        world_pos
+        self.position + self.quaternion * local_pos
     }
 
     fn world_to_local(&self, world_pos: Vec3) -> Vec3 {
-        let local_pos =  self.inv_quaternion * (world_pos - self.position);
-        local_pos
+        self.inv_quaternion * (world_pos - self.position)
     }
 
-    fn get_inv_inertia(&self) -> Vec3{
+    fn prev_local_to_world(&self, local_pos: Vec3) -> Vec3 {
+        self.prev_pos + self.prev_quaternion * local_pos
+    }
+
+    fn get_inv_inertia(&self) -> Mat3{
         self.inv_inertia
     }
 
@@ -323,77 +478,102 @@ impl Simulate for RigidBody{
     }
 
     fn get_centroid_world_pos(&self) -> Vec3 {
-        self.centroid + self.position
+        self.position
     }
 }
-impl Simulate for CuboidBody {
-
-    fn step(&mut self, dt: f32, acceleration: Vec3){
-        self.rigid_body.step(dt, acceleration)
-    }
-
-    fn update_vel(&mut self, dt: f32) {
-        self.rigid_body.update_vel(dt)
-    }
-
-    fn _apply_correction(&mut self, corr: Vec3, pos: Vec3) {
-        self.rigid_body._apply_correction(corr, pos)
-    }
-
-    fn apply_correction(&mut self, compliance: f32, corr: Vec3, pos: Vec3, other_body:  Option<Mut<CuboidBody>>, other_pos: Vec3) -> f32 {
-        self.rigid_body.apply_correction(compliance, corr, pos, other_body, other_pos)
-    }
-
-
-
-
-    fn get_position(&mut self) -> Vec3 {
-        self.rigid_body.get_position()
-    }
-
-    fn get_velocity(&self) -> Vec3 {
-        self.rigid_body.get_velocity()
-    }
 
-    fn get_quat(&mut self) -> Quat {
-        self.rigid_body.get_quat()
+// 取出约束连接的两个刚体, body1 为 None 时连接到世界
+pub fn get_bodies(
+    bodies: &mut Arena<RigidBody>,
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
+) -> Option<(&mut RigidBody, Option<&mut RigidBody>)> {
+    match body1 {
+        Some(body1) => bodies.get2_mut(body0, body1).map(|(body_0, body_1)| (body_0, Some(body_1))),
+        None => bodies.get_mut(body0).map(|body_0| (body_0, None)),
     }
+}
 
-    fn get_angule_vel(&self) -> Vec3 {
-        self.rigid_body.get_angule_vel()
-
-    }
-
-    fn get_mass(&self) -> f32 {
-        self.rigid_body.get_mass()
-
-    }
-
-    fn get_mass_inv(&self, normal: Vec3, pos: Vec3) -> f32 {
-        self.rigid_body.get_mass_inv(normal, pos)
-
-    }
-
-
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn local_to_world(&self, local_pos: Vec3) -> Vec3 {
-        self.rigid_body.local_to_world(local_pos)
+    // 1×2×3 的长方体, 密度 2, 质量 12, 主惯量 (13, 10, 5)
+    fn cuboid(angles: Vec3) -> RigidBody {
+        RigidBody::new(Vec3::ZERO, Shape::Cuboid { size: vec3(1.0, 2.0, 3.0) }, angles, 2.0)
     }
 
-    fn world_to_local(&self, world_pos: Vec3) -> Vec3 {
-        self.rigid_body.world_to_local(world_pos)
+    fn principal_inertia() -> Vec3 {
+        vec3(13.0, 10.0, 5.0)
     }
 
-    fn get_inv_inertia(&self) -> Vec3 {
-        self.rigid_body.get_inv_inertia()
+    // 修正前后朝向之差, 用世界坐标下的旋转向量表示
+    fn rotation_delta(before: Quat, after: Quat) -> Vec3 {
+        (after * before.inverse()).to_scaled_axis()
     }
 
-    fn get_inertia(&self) -> Mat3 {
-        self.rigid_body.get_inertia()
+    #[test]
+    fn angular_mass_inv_about_principal_axes() {
+        let body = cuboid(Vec3::ZERO);
+        let inertia = principal_inertia();
+        for (axis, i) in [(Vec3::X, inertia.x), (Vec3::Y, inertia.y), (Vec3::Z, inertia.z)] {
+            let w = body.get_angular_mass_inv(axis);
+            assert!((w - 1.0 / i).abs() < 1e-6, "axis {axis}: {w} != {}", 1.0 / i);
+        }
     }
 
-    fn get_centroid_world_pos(&self) -> Vec3 {
-        self.rigid_body.get_centroid_world_pos()
+    #[test]
+    fn compliant_rotation_correction_matches_xpbd_step() {
+        let mut body = cuboid(Vec3::ZERO);
+        let before = body.get_quat();
+        // α 与 I⁻¹ 同量级, 修正量明显小于无柔度时
+        let (c, compliance, dt) = (1e-3, 1e-4, 1.0 / 60.0);
+        let w = 1.0 / principal_inertia().x;
+        let alpha = compliance / dt / dt;
+
+        body.apply_rotation_correction(compliance, Vec3::X * c, None, dt);
+
+        let expected = c * w / (w + alpha);
+        let delta = rotation_delta(before, body.get_quat());
+        assert!((delta.x - expected).abs() < 1e-6, "{} != {expected}", delta.x);
+        assert!(delta.y.abs() < 1e-6 && delta.z.abs() < 1e-6);
+    }
+
+    #[test]
+    fn rotated_body_uses_world_inertia() {
+        let angles = vec3(0.3, -0.7, 1.1);
+        let mut body = cuboid(angles);
+        let r = Mat3::from_quat(Quat::from_euler(EulerRot::XYZ, angles.x, angles.y, angles.z));
+        let inv_inertia_world = r * Mat3::from_diagonal(principal_inertia().recip()) * r.transpose();
+
+        let n = vec3(1.0, 2.0, -0.5).normalize();
+        let w = n.dot(inv_inertia_world * n);
+        assert!((body.get_angular_mass_inv(n) - w).abs() < 1e-6);
+
+        // 无柔度时转过的旋转向量为 I⁻¹ n · c / w, 一般不与 n 平行
+        let before = body.get_quat();
+        let c = 1e-3;
+        body.apply_rotation_correction(0.0, n * c, None, 1.0 / 60.0);
+        let expected = inv_inertia_world * n * (c / w);
+        let delta = rotation_delta(before, body.get_quat());
+        assert!((delta - expected).length() < 1e-6, "{delta} != {expected}");
+    }
+
+    #[test]
+    fn empty_compound_stays_finite() {
+        let mut body = RigidBody::new(Vec3::ZERO, Shape::Compound(Vec::new()), Vec3::ZERO, 2.0);
+        assert_eq!(body.get_mass_inv(Vec3::Y, Vec3::ZERO), 0.0);
+        body.set_mass(3.0);
+        assert_eq!(body.get_mass_inv(Vec3::Y, Vec3::ZERO), 0.0);
+        body.step(1.0 / 60.0, vec3(0.0, -9.8, 0.0));
+        assert!(body.get_position().is_finite() && body.get_quat().is_finite());
+    }
+
+    #[test]
+    fn non_positive_mass_factor_keeps_mass() {
+        let mut body = cuboid(Vec3::ZERO);
+        body.set_mass(0.0);
+        assert!((body.get_mass() - 12.0).abs() < 1e-5);
+        assert!((body.get_mass_inv(Vec3::Y, Vec3::ZERO) - 1.0 / 12.0).abs() < 1e-6);
     }
 }
-
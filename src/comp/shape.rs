@@ -0,0 +1,293 @@
+use crate::comp::convex_hull::{face_normal, ConvexHull};
+use glam::{vec3, Mat3, Quat, Vec3};
+use std::f32::consts::{PI, TAU};
+
+// 圆柱在碰撞中按正多棱柱处理的边数, 渲染网格使用相同的分辨率
+pub const CYLINDER_RESOLUTION: usize = 16;
+
+// 长方体 8 个顶点(与 Shape::vertices 顺序一致)组成的 6 个面
+const CUBOID_FACES: [[usize; 4]; 6] = [
+    [0, 3, 2, 1],
+    [4, 5, 6, 7],
+    [0, 1, 5, 4],
+    [3, 7, 6, 2],
+    [0, 4, 7, 3],
+    [1, 2, 6, 5],
+];
+
+// 组合体中的子形状, offset / rotation 为子形状在组合体坐标系下的位姿
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompoundChild {
+    pub shape: Shape,
+    pub offset: Vec3,
+    pub rotation: Quat,
+}
+
+impl CompoundChild {
+    pub fn new(shape: Shape, offset: Vec3, rotation: Quat) -> Self {
+        Self { shape, offset, rotation }
+    }
+
+    // 子形状质心在组合体坐标系下的位置
+    fn centroid(&self) -> Vec3 {
+        self.offset + self.rotation * self.shape.centroid()
+    }
+}
+
+// 碰撞用的凸块; 有面的是多面体, 没有面的是球/胶囊的核心点(线段)向外膨胀 radius
+// 面的平面和去重后的棱在创建时算好, 碰撞时只需变换到世界坐标
+#[derive(Clone, Debug)]
+pub struct ConvexPart {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<Vec<usize>>,
+    pub radius: f32,
+    // 每个面的外法线和偏移, 满足 dot(normal, x) <= offset 的点在面内侧
+    pub planes: Vec<(Vec3, f32)>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl ConvexPart {
+    pub fn new(vertices: Vec<Vec3>, faces: Vec<Vec<usize>>, radius: f32) -> Self {
+        let mut planes = Vec::with_capacity(faces.len());
+        let mut edges = Vec::new();
+        if !faces.is_empty() {
+            let center = vertices.iter().copied().sum::<Vec3>() / vertices.len() as f32;
+            for face in faces.iter() {
+                let a = vertices[face[0]];
+                let mut normal = face_normal(&vertices, face);
+                if normal.dot(a - center) < 0.0 {
+                    normal = -normal;
+                }
+                planes.push((normal, normal.dot(a)));
+                for i in 0..face.len() {
+                    let i0 = face[i];
+                    let i1 = face[(i + 1) % face.len()];
+                    edges.push((i0.min(i1), i0.max(i1)));
+                }
+            }
+            edges.sort_unstable();
+            edges.dedup();
+        }
+        Self { vertices, faces, radius, planes, edges }
+    }
+
+    pub fn is_polyhedron(&self) -> bool {
+        !self.faces.is_empty()
+    }
+
+    pub(crate) fn transformed(mut self, offset: Vec3, rotation: Quat) -> Self {
+        for v in self.vertices.iter_mut() {
+            *v = offset + rotation * *v;
+        }
+        for (normal, d) in self.planes.iter_mut() {
+            *normal = rotation * *normal;
+            *d += normal.dot(offset);
+        }
+        self
+    }
+}
+
+// 刚体的几何形状, 定义在局部坐标系下; 除组合体外均以质心为原点, 胶囊和圆柱沿局部 y 轴
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Cuboid { size: Vec3 },
+    Sphere { radius: f32 },
+    // 中间圆柱段长 2 * half_length, 两端为半径 radius 的半球(与 Capsule3d 一致)
+    Capsule { radius: f32, half_length: f32 },
+    Cylinder { radius: f32, height: f32 },
+    ConvexHull(ConvexHull),
+    Compound(Vec<CompoundChild>),
+}
+
+impl Shape {
+    pub fn volume(&self) -> f32 {
+        match *self {
+            Shape::Cuboid { size } => size.x * size.y * size.z,
+            Shape::Sphere { radius } => 4.0 / 3.0 * PI * radius * radius * radius,
+            Shape::Capsule { radius, half_length } => {
+                PI * radius * radius * (2.0 * half_length + 4.0 / 3.0 * radius)
+            }
+            Shape::Cylinder { radius, height } => PI * radius * radius * height,
+            Shape::ConvexHull(ref hull) => hull.volume,
+            Shape::Compound(ref children) => children.iter().map(|c| c.shape.volume()).sum(),
+        }
+    }
+
+    // 质心在形状坐标系下的位置, 只有组合体不在原点
+    pub fn centroid(&self) -> Vec3 {
+        match *self {
+            Shape::Compound(ref children) => {
+                let volume = self.volume();
+                if volume <= 0.0 {
+                    return Vec3::ZERO;
+                }
+                children.iter().map(|c| c.centroid() * c.shape.volume()).sum::<Vec3>() / volume
+            }
+            _ => Vec3::ZERO,
+        }
+    }
+
+    // 返回 (质量, 局部坐标下绕质心的转动惯量张量)
+    pub fn mass_properties(&self, density: f32) -> (f32, Mat3) {
+        let mass = density * self.volume();
+        let inertia = match *self {
+            Shape::Cuboid { size } => {
+                let ix = 1.0 / 12.0 * mass * (size.y * size.y + size.z * size.z);
+                let iy = 1.0 / 12.0 * mass * (size.x * size.x + size.z * size.z);
+                let iz = 1.0 / 12.0 * mass * (size.x * size.x + size.y * size.y);
+                Mat3::from_diagonal(vec3(ix, iy, iz))
+            }
+            Shape::Sphere { radius } => Mat3::from_diagonal(Vec3::splat(2.0 / 5.0 * mass * radius * radius)),
+            Shape::Capsule { radius, half_length } => {
+                let r = radius;
+                let h = 2.0 * half_length;
+                let m_cylinder = density * PI * r * r * h;
+                // 单个半球
+                let m_hemi = density * 2.0 / 3.0 * PI * r * r * r;
+                let iy = 0.5 * m_cylinder * r * r + 2.0 * (2.0 / 5.0 * m_hemi * r * r);
+                // 半球绕自身质心的转动惯量再用平行轴定理移到胶囊中心, 半球质心距平面 3r/8
+                let ix = m_cylinder * (h * h / 12.0 + r * r / 4.0)
+                    + 2.0 * m_hemi * (2.0 / 5.0 * r * r + h * h / 4.0 + 3.0 / 8.0 * h * r);
+                Mat3::from_diagonal(vec3(ix, iy, ix))
+            }
+            Shape::Cylinder { radius, height } => {
+                let ix = 1.0 / 12.0 * mass * (3.0 * radius * radius + height * height);
+                let iy = 0.5 * mass * radius * radius;
+                Mat3::from_diagonal(vec3(ix, iy, ix))
+            }
+            Shape::ConvexHull(ref hull) => hull.inertia * density,
+            Shape::Compound(ref children) => {
+                // 子形状的惯量转到组合体坐标系, 再用平行轴定理移到组合体质心
+                let centroid = self.centroid();
+                let mut inertia = Mat3::ZERO;
+                for child in children.iter() {
+                    let (child_mass, child_inertia) = child.shape.mass_properties(density);
+                    let rotation = Mat3::from_quat(child.rotation);
+                    let d = child.centroid() - centroid;
+                    let shift = Mat3::from_diagonal(Vec3::splat(d.length_squared()))
+                        - Mat3::from_cols(d * d.x, d * d.y, d * d.z);
+                    inertia += rotation * child_inertia * rotation.transpose() + shift * child_mass;
+                }
+                inertia
+            }
+        };
+        (mass, inertia)
+    }
+
+    fn radius(&self) -> f32 {
+        match *self {
+            Shape::Sphere { radius } | Shape::Capsule { radius, .. } => radius,
+            _ => 0.0,
+        }
+    }
+
+    // 多面体的顶点; 球和胶囊返回核心点
+    fn vertices(&self) -> Vec<Vec3> {
+        match *self {
+            Shape::Cuboid { size } => {
+                let ex = 0.5 * size.x;
+                let ey = 0.5 * size.y;
+                let ez = 0.5 * size.z;
+                vec![
+                    vec3(-ex, -ey, -ez),
+                    vec3(ex, -ey, -ez),
+                    vec3(ex, ey, -ez),
+                    vec3(-ex, ey, -ez),
+                    vec3(-ex, -ey, ez),
+                    vec3(ex, -ey, ez),
+                    vec3(ex, ey, ez),
+                    vec3(-ex, ey, ez),
+                ]
+            }
+            Shape::Sphere { .. } => vec![Vec3::ZERO],
+            Shape::Capsule { half_length, .. } => vec![vec3(0.0, -half_length, 0.0), vec3(0.0, half_length, 0.0)],
+            Shape::Cylinder { radius, height } => {
+                // 先下底面一圈, 再上底面一圈
+                let mut vertices = Vec::with_capacity(2 * CYLINDER_RESOLUTION);
+                for y in [-0.5 * height, 0.5 * height] {
+                    for i in 0..CYLINDER_RESOLUTION {
+                        let (sin, cos) = (i as f32 * TAU / CYLINDER_RESOLUTION as f32).sin_cos();
+                        vertices.push(vec3(radius * cos, y, radius * sin));
+                    }
+                }
+                vertices
+            }
+            Shape::ConvexHull(ref hull) => hull.vertices.clone(),
+            Shape::Compound(_) => Vec::new(),
+        }
+    }
+
+    // 多面体的面(顶点下标按环绕顺序); 球和胶囊没有面
+    fn faces(&self) -> Vec<Vec<usize>> {
+        match *self {
+            Shape::Cuboid { .. } => CUBOID_FACES.iter().map(|f| f.to_vec()).collect(),
+            Shape::Cylinder { .. } => {
+                let n = CYLINDER_RESOLUTION;
+                let mut faces = Vec::with_capacity(n + 2);
+                faces.push((0..n).collect());
+                faces.push((n..2 * n).rev().collect());
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    faces.push(vec![i, j, n + j, n + i]);
+                }
+                faces
+            }
+            Shape::ConvexHull(ref hull) => hull.faces.clone(),
+            Shape::Sphere { .. } | Shape::Capsule { .. } | Shape::Compound(_) => Vec::new(),
+        }
+    }
+
+    // 形状坐标系下的凸块, 组合体的每个子形状各占一块
+    pub fn parts(&self) -> Vec<ConvexPart> {
+        match *self {
+            Shape::Compound(ref children) => children.iter()
+                .flat_map(|c| c.shape.parts().into_iter().map(|p| p.transformed(c.offset, c.rotation)))
+                .collect(),
+            _ => vec![ConvexPart::new(self.vertices(), self.faces(), self.radius())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn cube(side: f32, offset: Vec3) -> CompoundChild {
+        CompoundChild::new(Shape::Cuboid { size: Vec3::splat(side) }, offset, Quat::IDENTITY)
+    }
+
+    #[test]
+    fn compound_of_offset_cubes_uses_parallel_axis() {
+        // 两个单位立方体相距 2, 质心在中点; 绕 x 轴各 1/6, 绕 y / z 轴再各加 m d² = 1
+        let shape = Shape::Compound(vec![cube(1.0, vec3(-1.0, 0.0, 0.0)), cube(1.0, vec3(1.0, 0.0, 0.0))]);
+        let (mass, inertia) = shape.mass_properties(1.0);
+        assert!((mass - 2.0).abs() < 1e-6);
+        assert!(shape.centroid().abs_diff_eq(Vec3::ZERO, 1e-6));
+        let expected = Mat3::from_diagonal(vec3(1.0 / 3.0, 7.0 / 3.0, 7.0 / 3.0));
+        assert!(inertia.abs_diff_eq(expected, 1e-5), "{inertia} != {expected}");
+    }
+
+    #[test]
+    fn compound_of_unequal_cubes() {
+        // 质量 1 和 8, 质心 x = 8 * 3 / 9 = 8/3, 两块到质心的距离为 8/3 和 1/3
+        let shape = Shape::Compound(vec![cube(1.0, Vec3::ZERO), cube(2.0, vec3(3.0, 0.0, 0.0))]);
+        let (mass, inertia) = shape.mass_properties(1.0);
+        assert!((mass - 9.0).abs() < 1e-5);
+        assert!(shape.centroid().abs_diff_eq(vec3(8.0 / 3.0, 0.0, 0.0), 1e-5));
+        let ix = 1.0 / 6.0 + 8.0 * 8.0 / 12.0;
+        let iy = 1.0 / 6.0 + 64.0 / 9.0 + 8.0 * 8.0 / 12.0 + 8.0 / 9.0;
+        let expected = Mat3::from_diagonal(vec3(ix, iy, iy));
+        assert!(inertia.abs_diff_eq(expected, 1e-4), "{inertia} != {expected}");
+    }
+
+    #[test]
+    fn compound_child_rotation_rotates_inertia() {
+        // 1×2×3 的长方体绕 z 转 90° 后相当于 2×1×3, 质量 6
+        let child = CompoundChild::new(Shape::Cuboid { size: vec3(1.0, 2.0, 3.0) }, Vec3::ZERO, Quat::from_rotation_z(FRAC_PI_2));
+        let (mass, inertia) = Shape::Compound(vec![child]).mass_properties(1.0);
+        assert!((mass - 6.0).abs() < 1e-5);
+        let expected = Mat3::from_diagonal(vec3(5.0, 6.5, 2.5));
+        assert!(inertia.abs_diff_eq(expected, 1e-5), "{inertia} != {expected}");
+    }
+}
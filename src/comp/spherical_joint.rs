@@ -0,0 +1,158 @@
+use crate::comp::arena::Arena;
+use crate::comp::joint::{limit_angle, sanitize_limits, world_dir, world_point, Breakable, BreakState, JointFrame, JointSensor};
+use crate::comp::physics_world::PhysicsWorld;
+use crate::comp::rigidbody::{get_bodies, BodyHandle, RigidBody, Simulate};
+use glam::Vec3;
+
+// 球铰: 两个锚点重合(零长度的双边约束), 可选的摆动锥角和扭转角限制, body1 为 None 时连接到世界
+pub struct SphericalJoint {
+    body0: BodyHandle,
+    body1: Option<BodyHandle>,
+    // 锚点, 扭转轴和参考方向的局部坐标, 参考方向用来计算扭转角
+    frame: JointFrame,
+    compliance: f32,
+    // 两个扭转轴之间允许的最大夹角, 单位弧度
+    swing_limit: Option<f32>,
+    // 绕扭转轴的角度限制 (min, max), 单位弧度
+    twist_limits: Option<(f32, f32)>,
+    break_state: BreakState,
+    // 最近一个子步结束时的读数
+    sensor: JointSensor,
+    world_pos0: Vec3,
+    world_pos1: Vec3,
+}
+
+impl SphericalJoint {
+    // anchor 和 axis 为世界坐标, 按两个刚体当前的位姿换算到局部坐标
+    pub fn new(
+        physics: &PhysicsWorld,
+        body0: BodyHandle,
+        body1: Option<BodyHandle>,
+        anchor: Vec3,
+        axis: Vec3,
+        compliance: f32,
+    ) -> Self {
+        Self {
+            body0,
+            body1,
+            frame: JointFrame::new(physics, body0, body1, anchor, axis),
+            compliance,
+            swing_limit: None,
+            twist_limits: None,
+            break_state: BreakState::default(),
+            sensor: JointSensor::default(),
+            world_pos0: anchor,
+            world_pos1: anchor,
+        }
+    }
+
+    // 从快照恢复时换成保存下来的局部坐标, 保留创建时的参考方向和相对朝向
+    pub fn set_frame(&mut self, frame: JointFrame) {
+        self.frame = frame;
+    }
+
+    pub fn get_frame(&self) -> JointFrame {
+        self.frame
+    }
+
+    pub fn get_compliance(&self) -> f32 {
+        self.compliance
+    }
+
+    // 负的锥角取 0, NaN 视为不限
+    pub fn set_swing_limit(&mut self, max_angle: f32) {
+        debug_assert!(max_angle >= 0.0, "swing limit {max_angle} is negative or NaN");
+        self.swing_limit = if max_angle.is_nan() { None } else { Some(max_angle.max(0.0)) };
+    }
+
+    // 创建时的扭转角为 0
+    pub fn set_twist_limits(&mut self, min: f32, max: f32) {
+        self.twist_limits = Some(sanitize_limits("twist limits", min, max));
+    }
+
+    pub fn get_swing_limit(&self) -> Option<f32> {
+        self.swing_limit
+    }
+
+    pub fn get_twist_limits(&self) -> Option<(f32, f32)> {
+        self.twist_limits
+    }
+
+    pub fn solve(&mut self, bodies: &mut Arena<RigidBody>, dt: f32) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, mut body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        // 摆动: 在两个扭转轴所在的平面内把夹角限制在锥角以内
+        if let Some(max_angle) = self.swing_limit {
+            let axis0 = body_0_ins.get_quat() * self.frame.axis0;
+            let axis1 = world_dir(&mut body_1_ins, self.frame.axis1);
+            let n = axis0.cross(axis1).normalize_or_zero();
+            if n != Vec3::ZERO {
+                if let Some(corr) = limit_angle(n, axis0, axis1, -max_angle, max_angle) {
+                    body_0_ins.apply_rotation_correction(self.compliance, corr, body_1_ins.as_deref_mut(), dt);
+                }
+            }
+        }
+
+        // 扭转: 绕两个扭转轴的平均方向比较参考方向
+        if let Some((min, max)) = self.twist_limits {
+            let q0 = body_0_ins.get_quat();
+            let axis0 = q0 * self.frame.axis0;
+            let axis1 = world_dir(&mut body_1_ins, self.frame.axis1);
+            let n = (axis0 + axis1).normalize_or_zero();
+            if n != Vec3::ZERO {
+                let ref0 = q0 * self.frame.ref0;
+                let ref1 = world_dir(&mut body_1_ins, self.frame.ref1);
+                let n1 = (ref0 - n * n.dot(ref0)).normalize_or_zero();
+                let n2 = (ref1 - n * n.dot(ref1)).normalize_or_zero();
+                if let Some(corr) = limit_angle(n, n1, n2, min, max) {
+                    body_0_ins.apply_rotation_correction(self.compliance, corr, body_1_ins.as_deref_mut(), dt);
+                }
+            }
+        }
+
+        // 锚点重合
+        self.world_pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        self.world_pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.break_state.force += body_0_ins.apply_correction(self.compliance, self.world_pos1 - self.world_pos0, self.world_pos0, body_1_ins, self.world_pos1, dt).abs();
+    }
+
+    // 速度阶段: 读数为两个锚点的分离和分离速度
+    pub fn solve_velocity(&mut self, bodies: &mut Arena<RigidBody>) {
+        if self.is_broken() {
+            return;
+        }
+        let Some((body_0_ins, body_1_ins)) = get_bodies(bodies, self.body0, self.body1) else {
+            return;
+        };
+        let pos0 = body_0_ins.local_to_world(self.frame.anchor0);
+        let pos1 = world_point(&body_1_ins, self.frame.anchor1);
+        self.sensor = JointSensor::measure(body_0_ins, &body_1_ins, pos0, pos1, (pos1 - pos0).normalize_or_zero(), self.break_state.force);
+    }
+
+    pub fn get_sensor(&self) -> JointSensor {
+        self.sensor
+    }
+
+    // 最近一次求解时两个锚点的世界坐标
+    pub fn get_world_positions(&self) -> (Vec3, Vec3) {
+        (self.world_pos0, self.world_pos1)
+    }
+}
+
+impl Breakable for SphericalJoint {
+    fn break_state(&self) -> &BreakState {
+        &self.break_state
+    }
+
+    fn break_state_mut(&mut self) -> &mut BreakState {
+        &mut self.break_state
+    }
+
+    fn get_body_handles(&self) -> (BodyHandle, Option<BodyHandle>) {
+        (self.body0, self.body1)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod scene;
+#[cfg(feature = "bevy")]
+pub mod world;
+pub mod comp;
+#[cfg(feature = "bevy")]
+pub mod ui;
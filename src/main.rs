@@ -5,16 +5,11 @@ use bevy::{
     window::{PresentMode, WindowTheme},
 };
 
-mod scene;
-mod world;
-mod comp;
-mod ui;
+use rust::ui::event::EventPlugin;
+use rust::ui::ui::UIPlugin;
 
-use crate::ui::event::EventPlugin;
-use crate::ui::ui::UIPlugin;
-
-use scene::camera::CameraControlPlugin;
-use world::world::WorldPlugin;
+use rust::scene::camera::CameraControlPlugin;
+use rust::world::world::WorldPlugin;
 fn main() {
     let mut app = App::new();
     let win_size = 1500.0;
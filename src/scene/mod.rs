@@ -1 +1,4 @@
-pub mod camera;
\ No newline at end of file
+#[cfg(feature = "bevy")]
+pub mod camera;
+
+pub mod scene_file;
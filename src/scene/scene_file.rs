@@ -0,0 +1,1059 @@
+use crate::comp::contact::GroundPlane;
+use crate::comp::convex_hull::ConvexHull;
+use crate::comp::distance_joint::{DistanceConstraint, DistanceMode};
+use crate::comp::fixed_joint::FixedJoint;
+use crate::comp::hinge_joint::HingeJoint;
+use crate::comp::joint::{Breakable, Joint, JointFrame, JointHandle, JointMotor};
+use crate::comp::physics_world::{PhysicsConfig, PhysicsWorld, GRAVITY};
+use crate::comp::prismatic_joint::PrismaticJoint;
+use crate::comp::rigidbody::{BodyHandle, RigidBody, Simulate};
+use crate::comp::shape::{CompoundChild, Shape};
+use crate::comp::spherical_joint::SphericalJoint;
+use glam::{EulerRot, Quat, Vec3};
+use log::warn;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 与 Bevy 的 AssetServer 相同的根目录: BEVY_ASSET_ROOT, cargo run 时的 CARGO_MANIFEST_DIR, 否则为可执行文件所在目录
+fn asset_root() -> PathBuf {
+    if let Ok(dir) = env::var("BEVY_ASSET_ROOT") {
+        return PathBuf::from(dir);
+    }
+    if let Ok(dir) = env::var("CARGO_MANIFEST_DIR") {
+        return PathBuf::from(dir);
+    }
+    env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+fn scenes_dir() -> PathBuf {
+    asset_root().join("assets").join("scenes")
+}
+
+// 场景文件 assets/scenes/<name>.ron 的路径
+pub fn scene_path(name: &str) -> PathBuf {
+    scenes_dir().join(format!("{name}.ron"))
+}
+
+// 新快照的路径, 文件名里带上保存时刻 (毫秒), 不会覆盖之前的快照
+pub fn new_snapshot_path() -> PathBuf {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    unique_snapshot_path(&scenes_dir(), millis)
+}
+
+// dir 里还不存在的 snapshot_<millis>.ron, 同一毫秒里再存时依次加序号
+fn unique_snapshot_path(dir: &Path, millis: u128) -> PathBuf {
+    let mut path = dir.join(format!("snapshot_{millis}.ron"));
+    let mut count = 1;
+    while path.exists() {
+        path = dir.join(format!("snapshot_{millis}_{count}.ron"));
+        count += 1;
+    }
+    path
+}
+
+// 最近保存的快照
+pub fn latest_snapshot() -> Option<PathBuf> {
+    std::fs::read_dir(scenes_dir()).ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            name.starts_with("snapshot_") && name.ends_with(".ron")
+        })
+        .max()
+}
+
+// 场景描述: 刚体, 关节, 重力, 风和地面; 关节用刚体在 bodies 里的序号引用刚体
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SceneFile {
+    #[serde(default = "default_gravity")]
+    pub gravity: Vec3,
+    #[serde(default)]
+    pub wind: Vec3,
+    #[serde(default)]
+    pub ground: GroundPlane,
+    // 求解参数, 省略时保持当前的设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestep: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub substeps: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<usize>,
+    #[serde(default)]
+    pub bodies: Vec<BodyDesc>,
+    #[serde(default)]
+    pub joints: Vec<JointDesc>,
+}
+
+fn default_gravity() -> Vec3 {
+    GRAVITY
+}
+
+fn default_density() -> f32 {
+    20.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BodyDesc {
+    pub shape: ShapeDesc,
+    // 形状坐标系原点的世界坐标和 XYZ 顺序的欧拉角, 与 RigidBody::new 的参数一致
+    pub position: Vec3,
+    #[serde(default)]
+    pub rotation: Vec3,
+    #[serde(default = "default_density")]
+    pub density: f32,
+    // 与 add_body 的 b_static 相同: 载入后先休眠, 随后固定不动
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_static: bool,
+    // is_static 的刚体已经休眠的时间, 载入后接着计时
+    #[serde(default, skip_serializing_if = "is_zero_f32")]
+    pub sleep_elapsed: f32,
+    // 已经固定不动, 载入后不再运动
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub frozen: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub velocity: Vec3,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub angular_velocity: Vec3,
+    // (静摩擦系数, 动摩擦系数), 省略时用 RigidBody 的默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub friction: Option<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restitution: Option<f32>,
+    // 显示颜色 (r, g, b), 不影响物理, 省略时用默认的蓝色
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<(f32, f32, f32)>,
+}
+
+impl BodyDesc {
+    // 其余参数取场景文件里省略时的默认值
+    pub fn new(shape: ShapeDesc, position: Vec3) -> Self {
+        BodyDesc {
+            shape,
+            position,
+            rotation: Vec3::ZERO,
+            density: default_density(),
+            is_static: false,
+            sleep_elapsed: 0.0,
+            frozen: false,
+            velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            friction: None,
+            restitution: None,
+            color: None,
+        }
+    }
+
+    // time 为创建时刻 (物理世界的模拟时间), 静止刚体从这时开始休眠; 凸包的点退化时报错
+    pub fn to_body(&self, time: f32) -> Result<RigidBody, String> {
+        let mut body = RigidBody::new(self.position, Shape::try_from(&self.shape)?, self.rotation, self.density);
+        if self.is_static || self.frozen {
+            body.set_sleep(true);
+            body.sleep_time = time - self.sleep_elapsed;
+        }
+        body.set_static(self.frozen);
+        body.set_velocity(self.velocity);
+        body.set_angular_velocity(self.angular_velocity);
+        if let Some((static_friction, dynamic_friction)) = self.friction {
+            body.set_friction(static_friction, dynamic_friction);
+        }
+        if let Some(restitution) = self.restitution {
+            body.set_restitution(restitution);
+        }
+        Ok(body)
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_zero(value: &Vec3) -> bool {
+    *value == Vec3::ZERO
+}
+
+fn is_zero_f32(value: &f32) -> bool {
+    *value == 0.0
+}
+
+// 场景文件里的形状, 与 Shape 一一对应
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ShapeDesc {
+    Cuboid { size: Vec3 },
+    Sphere { radius: f32 },
+    Capsule { radius: f32, half_length: f32 },
+    Cylinder { radius: f32, height: f32 },
+    // 这些点的凸包, 形状坐标系原点取凸包的质心
+    ConvexHull { points: Vec<Vec3> },
+    Compound { children: Vec<ChildDesc> },
+}
+
+// 组合体的子形状, offset / rotation 为子形状在组合体坐标系下的位姿
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChildDesc {
+    pub shape: ShapeDesc,
+    pub offset: Vec3,
+    #[serde(default = "default_rotation")]
+    pub rotation: Quat,
+}
+
+fn default_rotation() -> Quat {
+    Quat::IDENTITY
+}
+
+impl ShapeDesc {
+    // 尺寸必须为正, 胶囊的半长可以为 0; 凸包的点不能退化, 组合体至少有一个子形状
+    fn check(&self) -> Result<(), String> {
+        let ok = match *self {
+            ShapeDesc::Cuboid { size } => size.cmpgt(Vec3::ZERO).all(),
+            ShapeDesc::Sphere { radius } => radius > 0.0,
+            ShapeDesc::Capsule { radius, half_length } => radius > 0.0 && half_length >= 0.0,
+            ShapeDesc::Cylinder { radius, height } => radius > 0.0 && height > 0.0,
+            ShapeDesc::ConvexHull { .. } => return Shape::try_from(self).map(|_| ()),
+            ShapeDesc::Compound { ref children } => {
+                if children.is_empty() {
+                    return Err("compound has no children".to_string());
+                }
+                return children.iter().try_for_each(|child| child.shape.check());
+            }
+        };
+        if ok { Ok(()) } else { Err(format!("{self:?} has a non-positive size")) }
+    }
+}
+
+impl TryFrom<&ShapeDesc> for Shape {
+    type Error = String;
+
+    fn try_from(shape: &ShapeDesc) -> Result<Self, Self::Error> {
+        Ok(match *shape {
+            ShapeDesc::Cuboid { size } => Shape::Cuboid { size },
+            ShapeDesc::Sphere { radius } => Shape::Sphere { radius },
+            ShapeDesc::Capsule { radius, half_length } => Shape::Capsule { radius, half_length },
+            ShapeDesc::Cylinder { radius, height } => Shape::Cylinder { radius, height },
+            ShapeDesc::ConvexHull { ref points } => match ConvexHull::from_points(points) {
+                Some(hull) => Shape::ConvexHull(hull),
+                None => return Err(format!("convex hull of {} points is degenerate", points.len())),
+            },
+            ShapeDesc::Compound { ref children } if children.is_empty() => return Err("compound has no children".to_string()),
+            ShapeDesc::Compound { ref children } => Shape::Compound(children.iter()
+                .map(|child| Ok(CompoundChild::new(Shape::try_from(&child.shape)?, child.offset, child.rotation)))
+                .collect::<Result<_, String>>()?),
+        })
+    }
+}
+
+impl From<&Shape> for ShapeDesc {
+    fn from(shape: &Shape) -> Self {
+        match *shape {
+            Shape::Cuboid { size } => ShapeDesc::Cuboid { size },
+            Shape::Sphere { radius } => ShapeDesc::Sphere { radius },
+            Shape::Capsule { radius, half_length } => ShapeDesc::Capsule { radius, half_length },
+            Shape::Cylinder { radius, height } => ShapeDesc::Cylinder { radius, height },
+            // 凸包的顶点已经以质心为原点, 重新求凸包得到同一个形状
+            Shape::ConvexHull(ref hull) => ShapeDesc::ConvexHull { points: hull.vertices.clone() },
+            Shape::Compound(ref children) => ShapeDesc::Compound {
+                children: children.iter()
+                    .map(|child| ChildDesc { shape: (&child.shape).into(), offset: child.offset, rotation: child.rotation })
+                    .collect(),
+            },
+        }
+    }
+}
+
+// 关节描述, 参数与各个关节的构造函数一致; body1 为 None 时连接到世界
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum JointDesc {
+    // anchor0 为 body0 的局部坐标, anchor1 为 body1 的局部坐标, 连接到世界时为世界坐标
+    Distance {
+        body0: usize,
+        #[serde(default)]
+        body1: Option<usize>,
+        anchor0: Vec3,
+        anchor1: Vec3,
+        rest_length: f32,
+        #[serde(default)]
+        compliance: f32,
+        mode: DistanceMode,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        break_force: Option<f32>,
+    },
+    // 以下关节的锚点和轴都是创建时的世界坐标; 快照另外保存 frame, 即创建时换算出的局部坐标, 载入时直接使用
+    Hinge {
+        body0: usize,
+        #[serde(default)]
+        body1: Option<usize>,
+        anchor: Vec3,
+        axis: Vec3,
+        #[serde(default)]
+        compliance: f32,
+        #[serde(default)]
+        limits: Option<(f32, f32)>,
+        #[serde(default)]
+        motor: Option<JointMotor>,
+        #[serde(default)]
+        break_force: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame: Option<JointFrame>,
+    },
+    Spherical {
+        body0: usize,
+        #[serde(default)]
+        body1: Option<usize>,
+        anchor: Vec3,
+        twist_axis: Vec3,
+        #[serde(default)]
+        compliance: f32,
+        #[serde(default)]
+        swing_limit: Option<f32>,
+        #[serde(default)]
+        twist_limits: Option<(f32, f32)>,
+        #[serde(default)]
+        break_force: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame: Option<JointFrame>,
+    },
+    Prismatic {
+        body0: usize,
+        #[serde(default)]
+        body1: Option<usize>,
+        anchor: Vec3,
+        axis: Vec3,
+        #[serde(default)]
+        compliance: f32,
+        #[serde(default)]
+        limits: Option<(f32, f32)>,
+        #[serde(default)]
+        motor: Option<JointMotor>,
+        #[serde(default)]
+        break_force: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame: Option<JointFrame>,
+    },
+    Fixed {
+        body0: usize,
+        #[serde(default)]
+        body1: Option<usize>,
+        anchor: Vec3,
+        #[serde(default)]
+        compliance: f32,
+        #[serde(default)]
+        angular_compliance: f32,
+        #[serde(default)]
+        break_force: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame: Option<JointFrame>,
+    },
+}
+
+// 限制区间的上下限必须是数, 下限不能大于上限
+fn check_limits(name: &str, limits: Option<(f32, f32)>) -> Result<(), String> {
+    match limits {
+        Some((min, max)) if min.is_nan() || max.is_nan() => Err(format!("{name} ({min}, {max}) are not numbers")),
+        Some((min, max)) if min > max => Err(format!("{name} ({min}, {max}) are inverted")),
+        _ => Ok(()),
+    }
+}
+
+// 关节会把轴归一化, 零向量或含 NaN / 无穷的轴得不到方向
+fn check_axis(name: &str, axis: Vec3) -> Result<(), String> {
+    match axis.try_normalize() {
+        Some(_) => Ok(()),
+        None => Err(format!("{name} {axis} has no direction")),
+    }
+}
+
+// 快照里的局部坐标直接使用, 同样不能含 NaN, 轴要有方向
+fn check_frame(frame: Option<JointFrame>) -> Result<(), String> {
+    let Some(frame) = frame else {
+        return Ok(());
+    };
+    if !(frame.anchor0.is_finite() && frame.anchor1.is_finite() && frame.rest_rotation.is_finite()) {
+        return Err(format!("frame {frame:?} is not finite"));
+    }
+    check_axis("frame axis0", frame.axis0)?;
+    check_axis("frame axis1", frame.axis1)?;
+    check_axis("frame ref0", frame.ref0)?;
+    check_axis("frame ref1", frame.ref1)
+}
+
+impl JointDesc {
+    // 会让关节崩溃或产生 NaN 的参数在载入时先检查, 坏的场景文件报错而不是崩溃
+    // 两端是同一个刚体的关节取不到两个刚体, 永远不会被求解, 也当作错误
+    fn check(&self) -> Result<(), String> {
+        let (body0, body1) = self.get_bodies();
+        if body1 == Some(body0) {
+            return Err(format!("connects body {body0} to itself"));
+        }
+        match *self {
+            JointDesc::Distance { mode, .. } => match mode {
+                DistanceMode::Range { min, max } => check_limits("range", Some((min, max))),
+                DistanceMode::Spring { stiffness, .. } if stiffness <= 0.0 => Err(format!("spring stiffness {stiffness} is not positive")),
+                _ => Ok(()),
+            },
+            JointDesc::Hinge { axis, limits, frame, .. } => {
+                check_axis("hinge axis", axis)?;
+                check_frame(frame)?;
+                check_limits("hinge limits", limits)
+            }
+            JointDesc::Spherical { twist_axis, swing_limit, twist_limits, frame, .. } => {
+                check_axis("twist axis", twist_axis)?;
+                check_frame(frame)?;
+                if let Some(swing_limit) = swing_limit {
+                    if swing_limit.is_nan() || swing_limit < 0.0 {
+                        return Err(format!("swing limit {swing_limit} is negative or not a number"));
+                    }
+                }
+                check_limits("twist limits", twist_limits)
+            }
+            JointDesc::Prismatic { axis, limits, frame, .. } => {
+                check_axis("prismatic axis", axis)?;
+                check_frame(frame)?;
+                check_limits("prismatic limits", limits)
+            }
+            JointDesc::Fixed { frame, .. } => check_frame(frame),
+        }
+    }
+
+    pub fn get_bodies(&self) -> (usize, Option<usize>) {
+        match *self {
+            JointDesc::Distance { body0, body1, .. }
+            | JointDesc::Hinge { body0, body1, .. }
+            | JointDesc::Spherical { body0, body1, .. }
+            | JointDesc::Prismatic { body0, body1, .. }
+            | JointDesc::Fixed { body0, body1, .. } => (body0, body1),
+        }
+    }
+
+    // 快照里的关节描述, body_0 为 body0 对应的刚体; 锚点和轴按当前位姿换算回世界坐标, 载入时以 frame 为准
+    fn from_joint(joint: &Joint, body_0: &RigidBody, body0: usize, body1: Option<usize>) -> JointDesc {
+        let break_force = joint.as_breakable().get_break_force();
+        // 连接到世界时锚点取世界上的固定点
+        let world = |frame: &JointFrame| {
+            let anchor = match body1 {
+                Some(_) => body_0.local_to_world(frame.anchor0),
+                None => frame.anchor1,
+            };
+            (anchor, body_0.get_quat() * frame.axis0)
+        };
+        match joint {
+            Joint::Distance(joint) => {
+                let (anchor0, anchor1) = joint.get_local_positions();
+                JointDesc::Distance {
+                    body0,
+                    body1,
+                    anchor0,
+                    anchor1,
+                    rest_length: joint.get_distance(),
+                    compliance: joint.get_compliance(),
+                    mode: joint.get_mode(),
+                    break_force,
+                }
+            }
+            Joint::Hinge(joint) => {
+                let frame = joint.get_frame();
+                let (anchor, axis) = world(&frame);
+                JointDesc::Hinge {
+                    body0,
+                    body1,
+                    anchor,
+                    axis,
+                    compliance: joint.get_compliance(),
+                    limits: joint.get_limits(),
+                    motor: joint.get_motor(),
+                    break_force,
+                    frame: Some(frame),
+                }
+            }
+            Joint::Spherical(joint) => {
+                let frame = joint.get_frame();
+                let (anchor, twist_axis) = world(&frame);
+                JointDesc::Spherical {
+                    body0,
+                    body1,
+                    anchor,
+                    twist_axis,
+                    compliance: joint.get_compliance(),
+                    swing_limit: joint.get_swing_limit(),
+                    twist_limits: joint.get_twist_limits(),
+                    break_force,
+                    frame: Some(frame),
+                }
+            }
+            Joint::Prismatic(joint) => {
+                let frame = joint.get_frame();
+                let (anchor, axis) = world(&frame);
+                JointDesc::Prismatic {
+                    body0,
+                    body1,
+                    anchor,
+                    axis,
+                    compliance: joint.get_compliance(),
+                    limits: joint.get_limits(),
+                    motor: joint.get_motor(),
+                    break_force,
+                    frame: Some(frame),
+                }
+            }
+            Joint::Fixed(joint) => {
+                let frame = joint.get_frame();
+                let (anchor, _) = world(&frame);
+                let (compliance, angular_compliance) = joint.get_compliances();
+                JointDesc::Fixed {
+                    body0,
+                    body1,
+                    anchor,
+                    compliance,
+                    angular_compliance,
+                    break_force,
+                    frame: Some(frame),
+                }
+            }
+        }
+    }
+
+    // 连接到世界的关节在世界上的固定点
+    pub fn world_anchor(&self) -> Option<Vec3> {
+        match *self {
+            JointDesc::Distance { body1: None, anchor1, .. } => Some(anchor1),
+            JointDesc::Hinge { body1: None, anchor, .. }
+            | JointDesc::Spherical { body1: None, anchor, .. }
+            | JointDesc::Prismatic { body1: None, anchor, .. }
+            | JointDesc::Fixed { body1: None, anchor, .. } => Some(anchor),
+            _ => None,
+        }
+    }
+
+    // bodies 为场景里每个刚体的句柄, 按 SceneFile::bodies 的顺序; 锚点和轴按刚体在 physics 里当前的位姿换算
+    fn to_joint(&self, physics: &PhysicsWorld, bodies: &[BodyHandle]) -> Joint {
+        let (body0, body1) = self.get_bodies();
+        let body0 = bodies[body0];
+        let body1 = body1.map(|body1| bodies[body1]);
+        match *self {
+            JointDesc::Distance { anchor0, anchor1, rest_length, compliance, mode, break_force, .. } => {
+                let mut joint = DistanceConstraint::new(body0, body1, anchor0, anchor1, rest_length, compliance, mode);
+                if let Some(break_force) = break_force {
+                    joint.set_break_force(break_force);
+                }
+                joint.into()
+            }
+            JointDesc::Hinge { anchor, axis, compliance, limits, motor, break_force, frame, .. } => {
+                let mut joint = HingeJoint::new(physics, body0, body1, anchor, axis, compliance);
+                if let Some(frame) = frame {
+                    joint.set_frame(frame);
+                }
+                if let Some((min, max)) = limits {
+                    joint.set_limits(min, max);
+                }
+                if let Some(motor) = motor {
+                    joint.set_motor(motor);
+                }
+                if let Some(break_force) = break_force {
+                    joint.set_break_force(break_force);
+                }
+                joint.into()
+            }
+            JointDesc::Spherical { anchor, twist_axis, compliance, swing_limit, twist_limits, break_force, frame, .. } => {
+                let mut joint = SphericalJoint::new(physics, body0, body1, anchor, twist_axis, compliance);
+                if let Some(frame) = frame {
+                    joint.set_frame(frame);
+                }
+                if let Some(max_angle) = swing_limit {
+                    joint.set_swing_limit(max_angle);
+                }
+                if let Some((min, max)) = twist_limits {
+                    joint.set_twist_limits(min, max);
+                }
+                if let Some(break_force) = break_force {
+                    joint.set_break_force(break_force);
+                }
+                joint.into()
+            }
+            JointDesc::Prismatic { anchor, axis, compliance, limits, motor, break_force, frame, .. } => {
+                let mut joint = PrismaticJoint::new(physics, body0, body1, anchor, axis, compliance);
+                if let Some(frame) = frame {
+                    joint.set_frame(frame);
+                }
+                if let Some((min, max)) = limits {
+                    joint.set_limits(min, max);
+                }
+                if let Some(motor) = motor {
+                    joint.set_motor(motor);
+                }
+                if let Some(break_force) = break_force {
+                    joint.set_break_force(break_force);
+                }
+                joint.into()
+            }
+            JointDesc::Fixed { anchor, compliance, angular_compliance, break_force, frame, .. } => {
+                let mut joint = FixedJoint::new(physics, body0, body1, anchor, compliance, angular_compliance);
+                if let Some(frame) = frame {
+                    joint.set_frame(frame);
+                }
+                if let Some(break_force) = break_force {
+                    joint.set_break_force(break_force);
+                }
+                joint.into()
+            }
+        }
+    }
+}
+
+// 求解参数的规则, 场景文件和 simulate 的命令行共用: 时间步长为有限正数, 子步数和迭代次数至少为 1
+pub fn check_solver(timestep: Option<f32>, substeps: Option<usize>, iterations: Option<usize>) -> Result<(), SceneError> {
+    if let Some(timestep) = timestep {
+        if !timestep.is_finite() || timestep <= 0.0 {
+            return Err(SceneError::InvalidConfig(format!("timestep {timestep} is not a positive number")));
+        }
+    }
+    if substeps == Some(0) || iterations == Some(0) {
+        return Err(SceneError::InvalidConfig("substeps and iterations must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Format(ron::Error),
+    // 第 joint 个关节引用了不存在的刚体
+    MissingBody { joint: usize, body: usize },
+    // 第 body 个刚体或第 joint 个关节的参数无效
+    InvalidBody { body: usize, reason: String },
+    InvalidJoint { joint: usize, reason: String },
+    // 求解参数无效
+    InvalidConfig(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{err}"),
+            SceneError::Parse(err) => write!(f, "{err}"),
+            SceneError::Format(err) => write!(f, "{err}"),
+            SceneError::MissingBody { joint, body } => write!(f, "joint {joint} refers to missing body {body}"),
+            SceneError::InvalidBody { body, reason } => write!(f, "body {body}: {reason}"),
+            SceneError::InvalidJoint { joint, reason } => write!(f, "joint {joint}: {reason}"),
+            SceneError::InvalidConfig(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        SceneError::Parse(err)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(err: ron::Error) -> Self {
+        SceneError::Format(err)
+    }
+}
+
+impl SceneFile {
+    // 场景里的重力, 风和给出的求解参数写到 config
+    pub fn apply_config(&self, config: &mut PhysicsConfig) {
+        config.gravity = self.gravity;
+        config.wind = self.wind;
+        if let Some(timestep) = self.timestep {
+            config.timestep = timestep;
+        }
+        if let Some(substeps) = self.substeps {
+            config.num_sub_steps = substeps;
+        }
+        if let Some(iterations) = self.iterations {
+            config.num_iterations = iterations;
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<SceneFile, SceneError> {
+        let text = std::fs::read_to_string(path)?;
+        let scene: SceneFile = ron::from_str(&text)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    fn validate(&self) -> Result<(), SceneError> {
+        check_solver(self.timestep, self.substeps, self.iterations)?;
+        for (body, desc) in self.bodies.iter().enumerate() {
+            if desc.density <= 0.0 {
+                return Err(SceneError::InvalidBody { body, reason: format!("density {} is not positive", desc.density) });
+            }
+            desc.shape.check().map_err(|reason| SceneError::InvalidBody { body, reason })?;
+        }
+        for (joint, desc) in self.joints.iter().enumerate() {
+            desc.check().map_err(|reason| SceneError::InvalidJoint { joint, reason })?;
+            let (body0, body1) = desc.get_bodies();
+            for body in std::iter::once(body0).chain(body1) {
+                if body >= self.bodies.len() {
+                    return Err(SceneError::MissingBody { joint, body });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 把刚体和关节加到物理世界里, 返回的句柄与 bodies 和 joints 一一对应; 静止刚体从物理世界的当前时刻开始休眠
+    // 先检查一遍, 出错时物理世界不变
+    pub fn build(&self, physics: &mut PhysicsWorld) -> Result<(Vec<BodyHandle>, Vec<JointHandle>), SceneError> {
+        self.validate()?;
+        let time = physics.time();
+        let bodies = self.bodies.iter().enumerate()
+            .map(|(body, desc)| desc.to_body(time).map_err(|reason| SceneError::InvalidBody { body, reason }))
+            .collect::<Result<Vec<RigidBody>, SceneError>>()?;
+        let bodies: Vec<BodyHandle> = bodies.into_iter().map(|body| physics.add_body(body)).collect();
+        let joints = self.joints.iter()
+            .map(|desc| {
+                let joint = desc.to_joint(physics, &bodies);
+                physics.add_joint(joint)
+            })
+            .collect();
+        Ok((bodies, joints))
+    }
+
+    // 物理世界当前状态的快照, 刚体用它在 bodies 里的序号作为编号; 显示颜色不在物理世界里, 不保存
+    pub fn snapshot(physics: &PhysicsWorld) -> SceneFile {
+        let config = physics.config;
+        let mut scene = SceneFile {
+            gravity: config.gravity,
+            wind: config.wind,
+            ground: physics.ground,
+            timestep: Some(config.timestep),
+            substeps: Some(config.num_sub_steps),
+            iterations: Some(config.num_iterations),
+            ..Default::default()
+        };
+        let mut ids: HashMap<BodyHandle, usize> = HashMap::new();
+        for (handle, body) in physics.bodies() {
+            let q = body.get_quat();
+            let (x, y, z) = q.to_euler(EulerRot::XYZ);
+            let is_static = body.is_sleep() && !body.is_static();
+            ids.insert(handle, scene.bodies.len());
+            scene.bodies.push(BodyDesc {
+                shape: body.get_shape().into(),
+                // 组合体的质心不在形状坐标系原点
+                position: body.get_position() - q * body.get_centroid(),
+                rotation: Vec3::new(x, y, z),
+                density: body.get_density(),
+                is_static,
+                sleep_elapsed: if is_static { physics.time() - body.sleep_time } else { 0.0 },
+                frozen: body.is_static(),
+                velocity: body.get_velocity(),
+                angular_velocity: body.get_angule_vel(),
+                friction: Some((body.get_static_friction(), body.get_dynamic_friction())),
+                restitution: Some(body.get_restitution()),
+                color: None,
+            });
+        }
+        for (handle, joint) in physics.joints() {
+            let (body0, body1) = joint.as_breakable().get_body_handles();
+            // 刚体被删除后关节还留在世界里, 但不再求解, 也没法保存
+            let body1_removed = body1.is_some_and(|body1| !ids.contains_key(&body1));
+            let (Some(body_0), false) = (physics.body(body0), body1_removed) else {
+                warn!("snapshot skips joint {}: one of its bodies was removed", handle.index());
+                continue;
+            };
+            let id1 = body1.map(|body1| ids[&body1]);
+            scene.joints.push(JointDesc::from_joint(joint, body_0, ids[&body0], id1));
+        }
+        scene
+    }
+
+    // 每个刚体和关节各占一行
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::new().depth_limit(2))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec3;
+
+    fn two_boxes(joint: JointDesc) -> SceneFile {
+        let shape = ShapeDesc::Cuboid { size: Vec3::splat(0.2) };
+        SceneFile {
+            bodies: vec![BodyDesc::new(shape.clone(), vec3(0.0, 1.0, 0.0)), BodyDesc::new(shape, vec3(0.5, 1.0, 0.0))],
+            joints: vec![joint],
+            ..Default::default()
+        }
+    }
+
+    fn hinge(body1: Option<usize>, axis: Vec3, limits: Option<(f32, f32)>) -> JointDesc {
+        JointDesc::Hinge {
+            body0: 0, body1, anchor: vec3(0.25, 1.0, 0.0), axis, compliance: 0.0,
+            limits, motor: None, break_force: None, frame: None,
+        }
+    }
+
+    fn spherical(twist_axis: Vec3, swing_limit: Option<f32>, twist_limits: Option<(f32, f32)>) -> JointDesc {
+        JointDesc::Spherical {
+            body0: 0, body1: Some(1), anchor: vec3(0.25, 1.0, 0.0), twist_axis, compliance: 0.0,
+            swing_limit, twist_limits, break_force: None, frame: None,
+        }
+    }
+
+    fn prismatic(axis: Vec3, limits: Option<(f32, f32)>) -> JointDesc {
+        JointDesc::Prismatic {
+            body0: 0, body1: Some(1), anchor: vec3(0.25, 1.0, 0.0), axis, compliance: 0.0,
+            limits, motor: None, break_force: None, frame: None,
+        }
+    }
+
+    // 出错时返回原因, 物理世界里不应留下任何刚体
+    fn rejected_joint(joint: JointDesc) -> String {
+        let mut physics = PhysicsWorld::new();
+        let result = two_boxes(joint).build(&mut physics);
+        assert_eq!(physics.bodies().count(), 0);
+        match result {
+            Err(SceneError::InvalidJoint { joint: 0, reason }) => reason,
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("joint was accepted"),
+        }
+    }
+
+    #[test]
+    fn valid_joints_build() {
+        for joint in [
+            hinge(Some(1), Vec3::Z, Some((-1.0, 1.0))),
+            hinge(None, Vec3::Y, None),
+            spherical(Vec3::X, Some(0.5), Some((-0.2, 0.2))),
+            prismatic(Vec3::X, Some((0.0, 0.3))),
+        ] {
+            let mut physics = PhysicsWorld::new();
+            let (bodies, joints) = two_boxes(joint).build(&mut physics).unwrap();
+            assert_eq!((bodies.len(), joints.len()), (2, 1));
+        }
+    }
+
+    #[test]
+    fn rejects_axes_without_direction() {
+        for axis in [Vec3::ZERO, vec3(f32::NAN, 0.0, 1.0), vec3(f32::INFINITY, 0.0, 0.0)] {
+            assert!(rejected_joint(hinge(Some(1), axis, None)).contains("hinge axis"));
+            assert!(rejected_joint(spherical(axis, None, None)).contains("twist axis"));
+            assert!(rejected_joint(prismatic(axis, None)).contains("prismatic axis"));
+        }
+    }
+
+    #[test]
+    fn rejects_nan_limits() {
+        for limits in [(f32::NAN, 1.0), (-1.0, f32::NAN)] {
+            assert!(rejected_joint(hinge(Some(1), Vec3::Z, Some(limits))).contains("not numbers"));
+            assert!(rejected_joint(spherical(Vec3::X, None, Some(limits))).contains("not numbers"));
+            assert!(rejected_joint(prismatic(Vec3::X, Some(limits))).contains("not numbers"));
+            let (min, max) = limits;
+            let range = JointDesc::Distance {
+                body0: 0, body1: Some(1), anchor0: Vec3::ZERO, anchor1: Vec3::ZERO, rest_length: 0.5,
+                compliance: 0.0, mode: DistanceMode::Range { min, max }, break_force: None,
+            };
+            assert!(rejected_joint(range).contains("not numbers"));
+        }
+        assert!(rejected_joint(spherical(Vec3::X, Some(f32::NAN), None)).contains("swing limit"));
+        assert!(rejected_joint(hinge(Some(1), Vec3::Z, Some((1.0, -1.0)))).contains("inverted"));
+    }
+
+    #[test]
+    fn rejects_joint_to_the_same_body() {
+        assert!(rejected_joint(hinge(Some(0), Vec3::Z, None)).contains("itself"));
+        let fixed = JointDesc::Fixed {
+            body0: 1, body1: Some(1), anchor: Vec3::ZERO, compliance: 0.0, angular_compliance: 0.0,
+            break_force: None, frame: None,
+        };
+        assert!(rejected_joint(fixed).contains("itself"));
+    }
+
+    #[test]
+    fn rejects_frame_without_direction() {
+        let mut physics = PhysicsWorld::new();
+        let body = physics.add_body(RigidBody::new(Vec3::ZERO, Shape::Sphere { radius: 0.1 }, Vec3::ZERO, 1.0));
+        let mut frame = JointFrame::new(&physics, body, None, Vec3::ZERO, Vec3::Y);
+        frame.axis1 = Vec3::ZERO;
+        let joint = JointDesc::Hinge {
+            body0: 0, body1: None, anchor: Vec3::ZERO, axis: Vec3::Y, compliance: 0.0,
+            limits: None, motor: None, break_force: None, frame: Some(frame),
+        };
+        assert!(rejected_joint(joint).contains("frame axis1"));
+    }
+
+    fn assert_close(a: Vec3, b: Vec3, what: &str) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{what}: {a} != {b}");
+    }
+
+    fn assert_frames_close(a: JointFrame, b: JointFrame) {
+        for (x, y, what) in [
+            (a.anchor0, b.anchor0, "anchor0"), (a.anchor1, b.anchor1, "anchor1"), (a.axis0, b.axis0, "axis0"),
+            (a.axis1, b.axis1, "axis1"), (a.ref0, b.ref0, "ref0"), (a.ref1, b.ref1, "ref1"),
+        ] {
+            assert_close(x, y, what);
+        }
+        assert!(a.rest_rotation.abs_diff_eq(b.rest_rotation, 1e-5));
+    }
+
+    // 各种形状, 休眠计时和冻结的刚体, 以及每种关节都带上限位, 马达和断裂力
+    fn busy_world() -> PhysicsWorld {
+        let mut physics = PhysicsWorld::new();
+        physics.config = PhysicsConfig {
+            timestep: 1.0 / 120.0, num_sub_steps: 4, num_iterations: 2,
+            gravity: vec3(0.0, -5.0, 0.0), wind: vec3(0.5, 0.0, 0.0),
+        };
+        physics.ground.height = -0.5;
+        physics.ground.restitution = 0.3;
+        let points = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z, vec3(0.4, 0.4, 0.4)].map(|p| p * 0.3);
+        let hull = Shape::ConvexHull(ConvexHull::from_points(&points).unwrap());
+        let cube = |size: f32| Shape::Cuboid { size: Vec3::splat(size) };
+        let compound = Shape::Compound(vec![
+            CompoundChild { shape: cube(0.2), offset: vec3(0.2, 0.0, 0.0), rotation: Quat::IDENTITY },
+            CompoundChild { shape: cube(0.1), offset: vec3(-0.1, 0.1, 0.0), rotation: Quat::from_rotation_z(0.4) },
+        ]);
+        let mut bodies = vec![
+            RigidBody::new(vec3(0.0, 2.0, 0.0), hull, vec3(0.1, 0.2, 0.3), 2.0),
+            RigidBody::new(vec3(0.6, 2.0, 0.0), compound, Vec3::ZERO, 1.0),
+            RigidBody::new(vec3(1.2, 2.0, 0.0), cube(0.2), vec3(0.0, 0.5, 0.0), 3.0),
+            RigidBody::new(vec3(1.8, 2.0, 0.0), Shape::Sphere { radius: 0.1 }, Vec3::ZERO, 1.0),
+            RigidBody::new(vec3(-1.0, 1.0, 0.0), cube(0.3), vec3(0.0, 0.0, 0.2), 1.0),
+        ];
+        bodies[2].set_velocity(vec3(0.3, 1.0, 0.0));
+        bodies[2].set_angular_velocity(vec3(0.0, 2.0, 0.0));
+        // 还在计时, 没到变成静止的时候
+        bodies[3].set_sleep(true);
+        bodies[4].set_sleep(true);
+        bodies[4].set_static(true);
+        for (i, body) in bodies.iter_mut().enumerate() {
+            body.set_friction(0.2 + 0.1 * i as f32, 0.1 + 0.1 * i as f32);
+            body.set_restitution(0.05 * i as f32);
+        }
+        let h: Vec<BodyHandle> = bodies.into_iter().map(|body| physics.add_body(body)).collect();
+
+        let mut hinge = HingeJoint::new(&physics, h[0], Some(h[1]), vec3(0.3, 2.0, 0.0), Vec3::Z, 1e-6);
+        hinge.set_limits(-0.5, 0.7);
+        hinge.set_motor(JointMotor::Velocity { target: 1.5, max_force: 20.0 });
+        hinge.set_break_force(1e6);
+        let mut spherical = SphericalJoint::new(&physics, h[1], Some(h[2]), vec3(0.9, 2.0, 0.0), Vec3::X, 2e-6);
+        spherical.set_swing_limit(0.6);
+        spherical.set_twist_limits(-0.3, 0.2);
+        let mut prismatic = PrismaticJoint::new(&physics, h[2], Some(h[3]), vec3(1.5, 2.0, 0.0), Vec3::X, 0.0);
+        prismatic.set_limits(-0.1, 0.4);
+        prismatic.set_motor(JointMotor::Position { target: 0.2, compliance: 1e-4, max_force: 50.0 });
+        let fixed = FixedJoint::new(&physics, h[4], None, vec3(-1.0, 1.2, 0.0), 1e-5, 3e-5);
+        let mut rope = DistanceConstraint::new(
+            h[0], None, vec3(0.0, 0.05, 0.0), vec3(0.0, 3.0, 0.0), 1.0, 0.0,
+            DistanceMode::Spring { stiffness: 80.0, damping: 1.5 },
+        );
+        rope.set_break_force(1e6);
+        physics.add_joint(hinge);
+        physics.add_joint(spherical);
+        physics.add_joint(prismatic);
+        physics.add_joint(fixed);
+        physics.add_joint(rope);
+        physics
+    }
+
+    #[test]
+    fn snapshot_round_trip_keeps_world_state() {
+        let mut physics = busy_world();
+        for _ in 0..10 {
+            assert!(physics.step().is_empty());
+        }
+        let path = env::temp_dir().join(format!("snapshot_round_trip_{}.ron", std::process::id()));
+        SceneFile::snapshot(&physics).save(&path).unwrap();
+        let scene = SceneFile::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let scene = scene.unwrap();
+
+        let mut restored = PhysicsWorld::new();
+        restored.ground = scene.ground;
+        scene.apply_config(&mut restored.config);
+        let (bodies, joints) = scene.build(&mut restored).unwrap();
+        assert_eq!(restored.config, physics.config);
+        assert_eq!(restored.ground, physics.ground);
+        assert_eq!((bodies.len(), joints.len()), (5, 5));
+
+        for ((_, a), &handle) in physics.bodies().zip(&bodies) {
+            let b = restored.body(handle).unwrap();
+            assert_close(a.get_position(), b.get_position(), "position");
+            assert!(a.get_quat().abs_diff_eq(b.get_quat(), 1e-5), "{} != {}", a.get_quat(), b.get_quat());
+            assert_eq!(a.get_velocity(), b.get_velocity());
+            assert_eq!(a.get_angule_vel(), b.get_angule_vel());
+            assert_eq!((a.is_sleep(), a.is_static()), (b.is_sleep(), b.is_static()));
+            // 冻结的刚体已经静止, 不再计时
+            if a.is_sleep() && !a.is_static() {
+                let (elapsed_a, elapsed_b) = (physics.time() - a.sleep_time, restored.time() - b.sleep_time);
+                assert!((elapsed_a - elapsed_b).abs() < 1e-5, "sleep elapsed {elapsed_a} != {elapsed_b}");
+            }
+            assert_eq!(a.get_density(), b.get_density());
+            assert!((a.get_mass() - b.get_mass()).abs() < 1e-5 * a.get_mass());
+            assert_eq!(a.get_static_friction(), b.get_static_friction());
+            assert_eq!(a.get_dynamic_friction(), b.get_dynamic_friction());
+            assert_eq!(a.get_restitution(), b.get_restitution());
+        }
+
+        for ((_, a), &handle) in physics.joints().zip(&joints) {
+            let b = restored.joint(handle).unwrap();
+            assert_eq!(a.as_breakable().get_break_force(), b.as_breakable().get_break_force());
+            match (a, b) {
+                (Joint::Hinge(a), Joint::Hinge(b)) => {
+                    assert_frames_close(a.get_frame(), b.get_frame());
+                    assert_eq!(a.get_compliance(), b.get_compliance());
+                    assert_eq!(a.get_limits(), b.get_limits());
+                    assert_eq!(a.get_motor(), b.get_motor());
+                }
+                (Joint::Spherical(a), Joint::Spherical(b)) => {
+                    assert_frames_close(a.get_frame(), b.get_frame());
+                    assert_eq!(a.get_compliance(), b.get_compliance());
+                    assert_eq!(a.get_swing_limit(), b.get_swing_limit());
+                    assert_eq!(a.get_twist_limits(), b.get_twist_limits());
+                }
+                (Joint::Prismatic(a), Joint::Prismatic(b)) => {
+                    assert_frames_close(a.get_frame(), b.get_frame());
+                    assert_eq!(a.get_compliance(), b.get_compliance());
+                    assert_eq!(a.get_limits(), b.get_limits());
+                    assert_eq!(a.get_motor(), b.get_motor());
+                }
+                (Joint::Fixed(a), Joint::Fixed(b)) => {
+                    assert_frames_close(a.get_frame(), b.get_frame());
+                    assert_eq!(a.get_compliances(), b.get_compliances());
+                }
+                (Joint::Distance(a), Joint::Distance(b)) => {
+                    let ((a0, a1), (b0, b1)) = (a.get_local_positions(), b.get_local_positions());
+                    assert_close(a0, b0, "distance anchor0");
+                    assert_close(a1, b1, "distance anchor1");
+                    assert_eq!(a.get_distance(), b.get_distance());
+                    assert_eq!(a.get_compliance(), b.get_compliance());
+                    assert_eq!(a.get_mode(), b.get_mode());
+                }
+                _ => panic!("joint type changed"),
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_paths_never_overwrite() {
+        let dir = env::temp_dir().join(format!("snapshot_paths_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut paths = Vec::new();
+        for _ in 0..3 {
+            let path = unique_snapshot_path(&dir, 1234);
+            assert!(!path.exists() && !paths.contains(&path), "{} was returned twice", path.display());
+            std::fs::write(&path, "").unwrap();
+            paths.push(path);
+        }
+        assert!(!unique_snapshot_path(&dir, 1234).exists());
+        assert_eq!(unique_snapshot_path(&dir, 1235), dir.join("snapshot_1235.ron"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
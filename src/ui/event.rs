@@ -35,10 +35,70 @@ fn handle_input(
         keyboard_input.just_pressed(KeyCode::Numpad3){
         new_event.message = "scene3".parse().unwrap();
     }
+    else if keyboard_input.just_pressed(KeyCode::Digit4) ||
+        keyboard_input.just_pressed(KeyCode::Numpad4){
+        new_event.message = "scene4".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit5) ||
+        keyboard_input.just_pressed(KeyCode::Numpad5){
+        new_event.message = "scene5".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit6) ||
+        keyboard_input.just_pressed(KeyCode::Numpad6){
+        new_event.message = "scene6".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit7) ||
+        keyboard_input.just_pressed(KeyCode::Numpad7){
+        new_event.message = "scene7".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit8) ||
+        keyboard_input.just_pressed(KeyCode::Numpad8){
+        new_event.message = "scene8".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit9) ||
+        keyboard_input.just_pressed(KeyCode::Numpad9){
+        new_event.message = "scene9".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::Digit0) ||
+        keyboard_input.just_pressed(KeyCode::Numpad0){
+        new_event.message = "scene10".parse().unwrap();
+    }
     else if keyboard_input.just_pressed(KeyCode::KeyP)
     {
         new_event.message = "pause".parse().unwrap();
     }
+    else if keyboard_input.just_pressed(KeyCode::KeyI)
+    {
+        new_event.message = "substeps+".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::KeyU)
+    {
+        new_event.message = "substeps-".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::KeyO)
+    {
+        new_event.message = "iterations+".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::KeyL)
+    {
+        new_event.message = "iterations-".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::KeyY)
+    {
+        new_event.message = "timestep+".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::KeyH)
+    {
+        new_event.message = "timestep-".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::F5)
+    {
+        new_event.message = "save".parse().unwrap();
+    }
+    else if keyboard_input.just_pressed(KeyCode::F9)
+    {
+        new_event.message = "load_snapshot".parse().unwrap();
+    }
     else if keyboard_input.pressed(KeyCode::KeyC)
     {
         new_event.message = "mass+".parse().unwrap();
@@ -47,6 +107,38 @@ fn handle_input(
     {
         new_event.message = "mass-".parse().unwrap();
     }
+    else if keyboard_input.pressed(KeyCode::KeyM)
+    {
+        new_event.message = "motor+".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyN)
+    {
+        new_event.message = "motor-".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyK)
+    {
+        new_event.message = "target+".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyJ)
+    {
+        new_event.message = "target-".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyR)
+    {
+        new_event.message = "slide_speed+".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyF)
+    {
+        new_event.message = "slide_speed-".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyT)
+    {
+        new_event.message = "slide_target+".parse().unwrap();
+    }
+    else if keyboard_input.pressed(KeyCode::KeyG)
+    {
+        new_event.message = "slide_target-".parse().unwrap();
+    }
     else if keyboard_input.pressed(KeyCode::ArrowUp)
     {
         new_event.message = "ArrowUp".parse().unwrap();
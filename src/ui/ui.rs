@@ -1,4 +1,5 @@
-use crate::world::world::Setting;
+use crate::comp::physics_world::PhysicsConfig;
+use crate::world::world::{SensorDisplay, SensorReadout, Setting};
 use bevy::ecs::system::Query;
 use bevy::prelude::*;
 use bevy::{
@@ -28,13 +29,28 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     '1' to enter  base scene.\n\
     '2' to enter chain scene.\n\
     '3' to enter fall chain scene.\n\
+    '4' to enter box pile scene.\n\
+    '5' to enter slope scene.\n\
+    '6' to enter shapes scene.\n\
+    '7' to enter convex hull scene.\n\
+    '8' to enter compound scene.\n\
+    '9' to enter T-handle scene.\n\
+    '0' to enter joint scene.\n\
     'p' to pause/unpause world.\n\
+    'i,u' to change substeps.\n\
+    'o,l' to change solver iterations.\n\
+    'y,h' to double/halve timestep.\n\
+    'F5' to save snapshot, 'F9' to load the latest.\n\
     ---------------------------------------\n\
     press & hold\n\
     'c'/'v' to increase/decrease density.\n\
     'up,down' to change wind in z.\n\
     'left,right' to change wind in x.\n\
     'pageup,pagedown' to change wind in y.\n\
+    'm,n' to change hinge motor speed (rad/s).\n\
+    'k,j' to change hinge motor target (rad).\n\
+    'r,f' to change slider motor speed (m/s).\n\
+    't,g' to change slider motor target (m).\n\
     'w,a,s,d,q,e,mouse right button' to control camera.\n\
     ";
 
@@ -143,6 +159,136 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         CustomText,
     ));
 
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "motor:",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                }
+            ),
+            TextSection::from_style(
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 30.0,
+                    color: GOLD.into(),
+                }
+            ),
+        ]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(95.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        CustomText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "broken joints:",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                }
+            ),
+            TextSection::from_style(
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 30.0,
+                    color: GOLD.into(),
+                }
+            ),
+        ]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(125.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        CustomText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "sensor:",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                }
+            ),
+            TextSection::from_style(
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 30.0,
+                    color: GOLD.into(),
+                }
+            ),
+        ]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(155.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        CustomText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "step:",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                }
+            ),
+            TextSection::from_style(
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 30.0,
+                    color: GOLD.into(),
+                }
+            ),
+        ]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(185.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        CustomText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "slider:",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                }
+            ),
+            TextSection::from_style(
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 30.0,
+                    color: GOLD.into(),
+                }
+            ),
+        ]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(215.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        CustomText,
+    ));
+
     commands.spawn((
         TextBundle::from_sections([
             TextSection::new(
@@ -165,7 +311,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         TextBundle::from_sections([
             TextSection::new(
-                "No Collision Version.",
+                "Collision Version.",
                 TextStyle {
                     font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                     font_size: 30.0,
@@ -199,6 +345,8 @@ fn text_update_system(
     mut query: Query<&mut Text, With<CustomText>>,
     // mut query_wind: Query<&mut Text, With<WindText>>,
     setting: ResMut<Setting>,
+    config: Res<PhysicsConfig>,
+    sensor_query: Query<(&SensorReadout, Has<SensorDisplay>)>,
 ) {
     for mut text in &mut query {
         if text.sections[0].value.starts_with("fps")
@@ -211,7 +359,7 @@ fn text_update_system(
         }
         else  if text.sections[0].value.starts_with("wind")
         {
-            let wind = setting.wind;
+            let wind = config.wind;
             let x= wind.x;
             let y= wind.y;
             let z= wind.z;
@@ -219,6 +367,44 @@ fn text_update_system(
             text.sections[1].value = format!("({x:.1}, {y:.1}, {z:.1})");
         }
 
+        else  if text.sections[0].value.starts_with("motor")
+        {
+            let speed = setting.motor_speed;
+            let target = setting.motor_target;
+            text.sections[1].value = format!("{speed:.1} rad/s {target:.2} rad");
+        }
+
+        else  if text.sections[0].value.starts_with("slider")
+        {
+            let speed = setting.slide_speed;
+            let target = setting.slide_target;
+            text.sections[1].value = format!("{speed:.2} m/s {target:.3} m");
+        }
+
+        else  if text.sections[0].value.starts_with("sensor")
+        {
+            // 只显示一个传感器的读数, 优先显示带 SensorDisplay 的关节
+            let shown = sensor_query.iter().find(|(_, display)| *display).or_else(|| sensor_query.iter().next());
+            text.sections[1].value = match shown {
+                Some((sensor, _)) => format!("L {:.3} T {:.3} v {:.3}", sensor.length, sensor.tension, sensor.axial_velocity),
+                None => "".to_string(),
+            };
+        }
+
+        else  if text.sections[0].value.starts_with("step")
+        {
+            let dt = config.timestep * 1000.0;
+            let sub_steps = config.num_sub_steps;
+            let iterations = config.num_iterations;
+            text.sections[1].value = format!("{dt:.1}ms x{sub_steps} iter {iterations}");
+        }
+
+        else  if text.sections[0].value.starts_with("broken")
+        {
+            let broken_joints = setting.broken_joints;
+            text.sections[1].value = format!("{broken_joints}");
+        }
+
         else  if text.sections[0].value.starts_with("density")
         {
             let mass_factor = setting.mass_factor;
@@ -233,6 +419,14 @@ fn text_update_system(
                 0 => { scene_name = "base scene."}
                 1 => { scene_name = "chain scene."}
                 2 => { scene_name = "fall chain scene."}
+                3 => { scene_name = "box pile scene."}
+                4 => { scene_name = "slope scene."}
+                5 => { scene_name = "shapes scene."}
+                6 => { scene_name = "convex hull scene."}
+                7 => { scene_name = "compound scene."}
+                8 => { scene_name = "T-handle scene."}
+                9 => { scene_name = "joint scene."}
+                10 => { scene_name = "scene file."}
                 _ => {}
             }
             text.sections[0].value = scene_name.parse().unwrap();
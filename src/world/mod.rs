@@ -1 +1,3 @@
-pub mod world;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod world;
+pub mod shape_mesh;
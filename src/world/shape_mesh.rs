@@ -0,0 +1,68 @@
+use crate::comp::convex_hull::{face_normal, ConvexHull};
+use crate::comp::shape::{Shape, CYLINDER_RESOLUTION};
+use bevy::math::Vec3;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::PrimitiveTopology;
+
+// 物理形状和 Bevy 网格之间的转换, 放在显示层, comp 里的物理代码不依赖渲染
+
+// 形状坐标系下的显示网格
+pub fn shape_mesh(shape: &Shape) -> Mesh {
+    match *shape {
+        Shape::Cuboid { size } => Cuboid::new(size.x, size.y, size.z).into(),
+        Shape::Sphere { radius } => Sphere::new(radius).into(),
+        Shape::Capsule { radius, half_length } => Capsule3d::new(radius, 2.0 * half_length).into(),
+        Shape::Cylinder { radius, height } => Cylinder::new(radius, height).mesh()
+            .resolution(CYLINDER_RESOLUTION as u32)
+            .build(),
+        Shape::ConvexHull(ref hull) => hull_mesh(hull),
+        Shape::Compound(ref children) => {
+            // 各子网格的属性不尽相同, 只保留位置和法线再合并
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, Vec::<[f32; 3]>::new())
+                .with_inserted_indices(Indices::U32(Vec::new()));
+            for child in children.iter() {
+                let child_mesh = shape_mesh(&child.shape)
+                    .transformed_by(Transform::from_translation(child.offset).with_rotation(child.rotation));
+                mesh.merge(&child_mesh);
+            }
+            mesh
+        }
+    }
+}
+
+// 平面着色的网格, 每个面单独一组顶点
+pub fn hull_mesh(hull: &ConvexHull) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for face in hull.faces.iter() {
+        let normal = face_normal(&hull.vertices, face);
+        let start = positions.len() as u32;
+        for i in face.iter() {
+            positions.push(hull.vertices[*i].to_array());
+            normals.push(normal.to_array());
+        }
+        for k in 1..(face.len() as u32 - 1) {
+            indices.extend_from_slice(&[start, start + k, start + k + 1]);
+        }
+    }
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+// 取网格的顶点位置求凸包
+pub fn hull_from_mesh(mesh: &Mesh) -> Option<ConvexHull> {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION)? {
+        VertexAttributeValues::Float32x3(positions) => {
+            let points: Vec<Vec3> = positions.iter().map(|p| Vec3::from_array(*p)).collect();
+            ConvexHull::from_points(&points)
+        }
+        _ => None,
+    }
+}
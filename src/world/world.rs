@@ -1,82 +1,215 @@
-use crate::comp::distance_joint::DistanceConstraint;
-use crate::comp::rigidbody::{CuboidBody, Simulate};
+use crate::comp::contact::GroundPlane;
+use crate::comp::convex_hull::ConvexHull;
+use crate::comp::distance_joint::{DistanceConstraint, DistanceMode};
+use crate::comp::hinge_joint::HingeJoint;
+use crate::comp::spherical_joint::SphericalJoint;
+use crate::comp::prismatic_joint::PrismaticJoint;
+use crate::comp::fixed_joint::FixedJoint;
+use crate::comp::joint::{Breakable, Joint, JointBroken, JointHandle, JointMotor, JointSensor};
+use crate::comp::physics_world::{PhysicsConfig, PhysicsWorld, GRAVITY};
+use crate::scene::scene_file::{latest_snapshot, new_snapshot_path, scene_path, BodyDesc, SceneFile, ShapeDesc};
+use crate::comp::rigidbody::{BodyHandle, RigidBody, Simulate};
+use crate::comp::shape::{CompoundChild, Shape};
 use crate::ui::event::MyEvent;
+use crate::world::shape_mesh::{hull_from_mesh, shape_mesh};
+use bevy::gizmos::GizmoPlugin;
 use bevy::math::vec3;
 use bevy::prelude::*;
 use rand::Rng;
-use std::thread;
-use std::time::Duration;
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::path::{Path, PathBuf};
 pub struct WorldPlugin;
 
+// 场景里的绳子稍微有些弹性
+const ROPE_COMPLIANCE: f32 = 0.001;
+
 #[derive(Resource)]
 pub struct Setting{
-    pub wind: Vec3,
     pub pause:bool,
     pub mass_factor:f32,
     pub start_up_time:f32,
     pub b_init:bool,
     pub scene_id:usize,
+    // 铰链电机的目标角速度 (rad/s) 和目标角度 (rad), 按键调节
+    pub motor_speed: f32,
+    pub motor_target: f32,
+    // 滑动关节电机的目标速度 (m/s) 和目标位移 (m), 与铰链分开调节
+    pub slide_speed: f32,
+    pub slide_target: f32,
+    // 当前场景里已经断开的关节数
+    pub broken_joints: usize,
 
 }
 impl Default for Setting {
     fn default() -> Self {
         Setting{
-            wind: vec3(-8.0, 0.0, -8.0),
             pause: false,
             mass_factor: 1.0,
             start_up_time: 0.0,
             b_init: false,
             scene_id: 0,
+            motor_speed: 2.0,
+            motor_target: 0.0,
+            slide_speed: 0.2,
+            slide_target: 0.0,
+            broken_joints: 0,
         }
     }
 }
 
+// 时间步长, 子步数, 迭代次数, 重力和风; 修改后由 apply_physics_config 同步到物理世界和固定时间步
+impl Resource for PhysicsConfig {}
+
+// 插件持有的物理世界, 场景和各个系统都通过它访问刚体和关节
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Physics(pub PhysicsWorld);
+
+// 显示刚体的实体, 每帧从物理世界同步位置和朝向
+#[derive(Component)]
+pub struct BodyRef(pub BodyHandle);
+
+// 刚体已经休眠, 显示成绿色
+#[derive(Component)]
+pub struct Asleep;
+
+// 显示关节标记的实体, 关节断开后被删除
+#[derive(Component)]
+pub struct JointRef(pub JointHandle);
+
+// 关节实体上的传感器读数, 每个固定步从物理世界更新
+#[derive(Component, Default, Deref)]
+pub struct SensorReadout(pub JointSensor);
+
+// 界面上优先显示这个关节的读数
+#[derive(Component)]
+pub struct SensorDisplay;
+
+// 关节被拉断时发出, entity 为已经删除的关节实体
+#[derive(Event)]
+pub struct JointBrokenEvent {
+    pub entity: Entity,
+    pub broken: JointBroken,
+}
+
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, init_world)
             .insert_resource(Setting::default()) // 插入资源
+            .insert_resource(PhysicsConfig { wind: vec3(-8.0, 0.0, -8.0), ..default() })
+            .init_resource::<Physics>()
             .add_systems(FixedPreUpdate, pre_tick)
-            .add_systems(FixedUpdate, tick)
-            .add_systems(FixedPostUpdate, show)
+            .add_systems(FixedUpdate, (tick, update_joint_sensors).chain())
+            .add_systems(FixedPostUpdate, (show, count_broken_joints))
             .add_event::<MyEvent>()
-            .add_systems(Update, handle_event);
+            .add_event::<JointBrokenEvent>()
+            .add_systems(Update, (handle_event, apply_physics_config).chain());
 
+        // 无窗口运行时没有 GizmoPlugin, 不画关节
+        if app.is_plugin_added::<GizmoPlugin>() {
+            app.add_systems(FixedPostUpdate, show_joints);
+        }
     }
 }
+// Bevy 系统的参数由调度器注入, 拆不开
+#[allow(clippy::too_many_arguments)]
 fn handle_event(mut event_reader: EventReader<MyEvent>,
                 mut setting: ResMut<Setting>,
+                mut config: ResMut<PhysicsConfig>,
                 mut commands: Commands, // 用于操作实体
                 mut query: Query<Entity, (Without<Text>, Without<Window>)>,
                 mut meshes: ResMut<Assets<Mesh>>,
                 mut materials: ResMut<Assets<StandardMaterial>>,
-                mut query1: Query<(&mut CuboidBody, &mut Transform)>,
-                time: Res<Time>)
+                mut physics: ResMut<Physics>)
 {
 
     if event_reader.is_empty() {return;}
     let  wind_factor = 0.2;
+    let  motor_factor = 0.05;
+    let  target_factor = 0.01;
+    let  slide_speed_factor = 0.01;
+    let  slide_target_factor = 0.002;
 
     for event in event_reader.read(){
         let msg =  event.message.clone();
         let mut mass_count = 0;
+        let mut scene_file = None;
+        if msg.starts_with("scene") {
+            setting.broken_joints = 0;
+        }
         match msg.as_str() {
             "scene1" => {
-                clear_scene(&mut commands,  &mut meshes, &mut materials,&mut query);
-                setting.wind = vec3(-8.0, 0.0, 8.0);
                 setting.scene_id = 0;
-                scene_base(&mut commands, &mut meshes, &mut materials, &mut query1);
+                load_scene(&mut commands, &mut meshes, &mut materials, &mut query, &mut physics, &mut config, &scene_path("base"));
             }
             "scene2" => {
-                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query);
-                setting.wind = vec3(-8.0, 0.0, -8.0);
                 setting.scene_id = 1;
-                scene_chain(&mut commands, &mut meshes, &mut materials, &mut query1);
+                load_scene(&mut commands, &mut meshes, &mut materials, &mut query, &mut physics, &mut config, &scene_path("chain"));
             }
             "scene3" => {
-                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query);
                 setting.scene_id = 2;
-                setting.wind = vec3(0.0, 0.0, 8.0);
-                scene_chain_timer(&mut commands, &mut meshes, &mut materials, &mut query1, &time);
+                load_scene(&mut commands, &mut meshes, &mut materials, &mut query, &mut physics, &mut config, &scene_path("chain_timer"));
+            }
+            "scene4" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 3;
+                config.gravity = GRAVITY;
+                config.wind = Vec3::ZERO;
+                scene_pile(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene5" => {
+                // 倾角 20°, tan(20°) ≈ 0.36, 摩擦系数低于它的箱子会滑下去
+                physics.ground = GroundPlane {
+                    normal: Quat::from_rotation_z(20.0_f32.to_radians()) * Vec3::Y,
+                    ..default()
+                };
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 4;
+                config.gravity = GRAVITY;
+                config.wind = Vec3::ZERO;
+                scene_slope(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene6" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 5;
+                config.gravity = GRAVITY;
+                config.wind = vec3(-4.0, 0.0, 0.0);
+                scene_shapes(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene7" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 6;
+                config.gravity = GRAVITY;
+                config.wind = Vec3::ZERO;
+                scene_hulls(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene8" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 7;
+                config.gravity = GRAVITY;
+                config.wind = Vec3::ZERO;
+                scene_compound(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene9" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 8;
+                // 失重环境下观察自由转动
+                config.gravity = Vec3::ZERO;
+                config.wind = Vec3::ZERO;
+                scene_t_handle(&mut commands, &mut meshes, &mut materials, &mut physics);
+            }
+            "scene10" => {
+                physics.ground = GroundPlane::default();
+                clear_scene(&mut commands,  &mut meshes, &mut materials, &mut query, &mut physics);
+                setting.scene_id = 9;
+                config.gravity = GRAVITY;
+                // 风把门吹到限位处
+                config.wind = vec3(0.0, 0.0, -3.0);
+                scene_joints(&mut commands, &mut meshes, &mut materials, &mut physics);
             }
             "mass+" => {
                 mass_count += 1;
@@ -88,46 +221,138 @@ fn handle_event(mut event_reader: EventReader<MyEvent>,
                 setting.pause = !setting.pause;
             }
             "ArrowUp" => {
-                setting.wind.z -= wind_factor;
+                config.wind.z -= wind_factor;
             }
             "ArrowDown" => {
-                setting.wind.z += wind_factor;
+                config.wind.z += wind_factor;
             }
             "ArrowLeft" => {
-                setting.wind.x -= wind_factor;
+                config.wind.x -= wind_factor;
             }
             "ArrowRight" => {
-                setting.wind.x += wind_factor;
+                config.wind.x += wind_factor;
             }
             "PageUp" => {
-                setting.wind.y += wind_factor;
+                config.wind.y += wind_factor;
             }
             "PageDown" => {
-                setting.wind.y -= wind_factor;
+                config.wind.y -= wind_factor;
+            }
+            "substeps+" => {
+                config.num_sub_steps = (config.num_sub_steps + 1).min(64);
+            }
+            "substeps-" => {
+                config.num_sub_steps = (config.num_sub_steps - 1).max(1);
+            }
+            "iterations+" => {
+                config.num_iterations = (config.num_iterations + 1).min(16);
+            }
+            "iterations-" => {
+                config.num_iterations = (config.num_iterations - 1).max(1);
+            }
+            "timestep+" => {
+                config.timestep = (config.timestep * 2.0).min(1.0 / 16.0);
+            }
+            "timestep-" => {
+                config.timestep = (config.timestep * 0.5).max(1.0 / 256.0);
+            }
+            "motor+" => {
+                setting.motor_speed += motor_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "motor-" => {
+                setting.motor_speed -= motor_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "target+" => {
+                setting.motor_target += target_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "target-" => {
+                setting.motor_target -= target_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "slide_speed+" => {
+                setting.slide_speed += slide_speed_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "slide_speed-" => {
+                setting.slide_speed -= slide_speed_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "slide_target+" => {
+                setting.slide_target += slide_target_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            "slide_target-" => {
+                setting.slide_target -= slide_target_factor;
+                drive_motors(&setting, &mut physics);
+            }
+            // 把当前状态存成场景文件, 之后可以再载入
+            "save" => {
+                let path = new_snapshot_path();
+                match SceneFile::snapshot(&physics).save(&path) {
+                    Ok(()) => info!("saved snapshot {}", path.display()),
+                    Err(err) => error!("failed to save snapshot {}: {err}", path.display()),
+                }
+            }
+            "load_snapshot" => {
+                scene_file = latest_snapshot();
+                if scene_file.is_none() {
+                    warn!("no snapshot to load");
+                }
+            }
+            // 按路径载入任意场景文件
+            file if file.starts_with("file:") => {
+                scene_file = Some(PathBuf::from(&file["file:".len()..]));
             }
             _ => {}
         }
 
+        if let Some(path) = scene_file {
+            setting.broken_joints = 0;
+            setting.scene_id = 10;
+            load_scene(&mut commands, &mut meshes, &mut materials, &mut query, &mut physics, &mut config, &path);
+        }
+
         if mass_count != 0 {
             setting.mass_factor *= 1.01_f32.powi(mass_count);
         }
     }
 }
 
+// PhysicsConfig 改变后同步到物理世界, FixedUpdate 的周期跟着 timestep 走, 每次 tick 正好前进一个 timestep
+fn apply_physics_config(
+    config: Res<PhysicsConfig>,
+    mut physics: ResMut<Physics>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+)
+{
+    if !config.is_changed() {
+        return;
+    }
+    physics.config = *config;
+    fixed_time.set_timestep_seconds(config.timestep as f64);
+}
+
 fn clear_scene(commands: &mut Commands, // 用于操作实体
                meshes:  &mut ResMut<Assets<Mesh>>,
                materials:  &mut ResMut<Assets<StandardMaterial>>,
                query: &mut Query<Entity, (Without<Text>, Without<Window>)>,
+               physics: &mut PhysicsWorld,
 )
 {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+    physics.clear();
+    let ground = &physics.ground;
 
     commands.spawn(PbrBundle {
         mesh: meshes.add(Circle::new(4.0)),
         material: materials.add(Color::WHITE),
-        transform: Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        transform: Transform::from_translation(ground.origin())
+            .with_rotation(Quat::from_rotation_arc(Vec3::Z, ground.normal)),
         ..default()
     });
 
@@ -177,316 +402,657 @@ fn init_world(mut event_writer: EventWriter<MyEvent>) {
 //     }
 //
 // }
-fn scene_base(mut commands: &mut Commands,
-           mut meshes:  &mut ResMut<Assets<Mesh>>,
-           mut materials:  &mut ResMut<Assets<StandardMaterial>>,
-           mut query:  &mut Query<(&mut CuboidBody, &mut Transform)>,
+fn scene_pile(commands: &mut Commands,
+              meshes: &mut ResMut<Assets<Mesh>>,
+              materials: &mut ResMut<Assets<StandardMaterial>>,
+              physics: &mut PhysicsWorld,
 )
 {
-    let mut init_pos = vec3(-1.6, 2.6, 0.0);
-    let origin_size = vec3(0.15, 0.15, 0.15);
-
-    let mut init_size = origin_size;
-
-    let mut connect_points:Vec<Vec3> = Vec::new();
-    connect_points.push(vec3(0.5, 0.5, 0.5));
-    connect_points.push(vec3(0.3, 0.5, 0.0));
-    connect_points.push(vec3(0.5, 0.5, 0.3));
-    connect_points.push(vec3(0.0, 0.0, 0.0));
-    connect_points.push(vec3(-0.3, 0.5, 0.5));
-    connect_points.push(vec3(-0.5, 0.5, 0.0));
-    connect_points.push(vec3(-0.5, 0.5, -0.3));
-
-    let num = connect_points.len();
-    let x_diff = 0.4;
-    let y_diff = 0.3;
-    let mut _idx = 0;
+    let box_size = vec3(0.12, 0.12, 0.12);
+    let spacing = 0.18;
+    let num_x = 6;
+    let num_z = 6;
+    let num_y = 9;
     let mut rng = rand::thread_rng();
-    for connect_point in connect_points.iter() {
-        let mut box_origin_pos = init_pos;
-        box_origin_pos.y += y_diff;
-        if _idx < num / 2 {
-            box_origin_pos.x -= x_diff;
-        }
-        else {
-            box_origin_pos.x += x_diff;
+
+    for level in 0..num_y {
+        for i in 0..num_x {
+            for k in 0..num_z {
+                let mut box_pos = vec3(
+                    (i as f32 - 0.5 * (num_x - 1) as f32) * spacing,
+                    0.3 + level as f32 * spacing,
+                    (k as f32 - 0.5 * (num_z - 1) as f32) * spacing,
+                );
+                box_pos.x += rng.gen_range(-0.02..0.02);
+                box_pos.z += rng.gen_range(-0.02..0.02);
+                add_body(commands, meshes, materials, physics, &BodyDesc::new(ShapeDesc::Cuboid { size: box_size }, box_pos));
+            }
         }
-        let entity = add_cuboid_body(&mut commands, &mut meshes, &mut materials, box_origin_pos, init_size, false, 0.0);
-        let  local_pos =  init_size * *connect_point;
+    }
+}
 
-        add_distance_joint(&mut commands, &mut meshes, &mut materials, entity, None, &mut query, init_pos, local_pos);
-        init_pos.x += 0.5;
-        if _idx < num / 2 {
-            init_pos.y -= 0.5;
-            init_size.x =  origin_size.x * rng.gen_range(0.7..2.6);
-            init_size.y =  origin_size.y * rng.gen_range(0.7..2.6);
-            init_size.z =  origin_size.z * rng.gen_range(0.7..2.6);
+fn scene_slope(commands: &mut Commands,
+               meshes: &mut ResMut<Assets<Mesh>>,
+               materials: &mut ResMut<Assets<StandardMaterial>>,
+               physics: &mut PhysicsWorld,
+)
+{
+    let ground = physics.ground;
+    let box_size = vec3(0.2, 0.2, 0.2);
+    let angle = ground.normal.x.atan2(ground.normal.y);
+    let up_hill = Quat::from_rotation_z(-angle) * Vec3::X;
+    let frictions = [0.1, 0.3, 0.6, 1.0];
 
-        }
-        else {
-            init_pos.y += 0.5;
-            init_size.x =  origin_size.x * rng.gen_range(0.7..2.6);
-            init_size.y =  origin_size.y * rng.gen_range(0.7..2.6);
-            init_size.z =  origin_size.z * rng.gen_range(0.7..2.6);
-        }
-        _idx += 1;
+    for (i, friction) in frictions.iter().enumerate() {
+        let box_pos = ground.origin() + up_hill * 1.0
+            + ground.normal * (0.5 * box_size.y + 0.001)
+            + Vec3::Z * (i as f32 - 1.5) * 0.4;
+        let mut body = RigidBody::new(box_pos, Shape::Cuboid { size: box_size }, vec3(0.0, 0.0, -angle), 20.0);
+        body.set_friction(*friction, *friction * 0.8);
+        let color = Color::srgb(1.0 - *friction * 0.8, 0.5, *friction);
+        spawn_body(commands, meshes, materials, physics, body, color);
     }
 }
 
-fn scene_chain(mut commands: &mut Commands,
-           mut meshes: &mut ResMut<Assets<Mesh>>,
-           mut materials: &mut ResMut<Assets<StandardMaterial>>,
-           mut query: &mut Query<(&mut CuboidBody, &mut Transform)>,
+// 四种形状各挂在一个固定点下摆动, 最后一个胶囊再串一个球
+fn scene_shapes(commands: &mut Commands,
+                meshes: &mut ResMut<Assets<Mesh>>,
+                materials: &mut ResMut<Assets<StandardMaterial>>,
+                physics: &mut PhysicsWorld,
 )
 {
-    let mut box_size = vec3(0.1, 0.1, 0.1);
-    let mut prev_y = 2.5;
-    let mut box_pos = vec3(0.0, prev_y, 0.0);
-    let dist = 0.2;
-    let mut prev_size = 0.0;
-    let mut prev_box: Option<Entity> = None;
-    let mut rng = rand::thread_rng();
+    let length = 0.5;
+    let density = 20.0;
+    let color = Color::srgb_u8(124, 144, 255);
+    let anchors = [vec3(-0.9, 2.5, 0.0), vec3(-0.3, 2.5, 0.0), vec3(0.3, 2.5, 0.0), vec3(0.9, 2.5, 0.0)];
 
-    for _level in 0..4 {
-        prev_y = box_pos.y;
-        box_pos.y -= dist + box_size.y;
-        if _level % 2 == 0 {
-            box_pos.x = 0.3;
-        }
-        else {
-            box_pos.x = -0.3;
-        }
-        let entity = add_cuboid_body(&mut commands, &mut meshes, &mut materials, box_pos, box_size, false, 0.0);
-        //println!("box_idx: {} y_pos:{} box_size:{}", _level,  box_pos.y,  box_size);
-        let length = (prev_y - box_pos.y - prev_size * 0.5 - box_size.y * 0.5).abs() * 1.2;
-        //println!("length: {} ", length);
+    // 形状及其沿 y 方向的半高
+    let shapes = [
+        (Shape::Cuboid { size: vec3(0.2, 0.2, 0.2) }, 0.1),
+        (Shape::Sphere { radius: 0.1 }, 0.1),
+        (Shape::Cylinder { radius: 0.08, height: 0.3 }, 0.15),
+        (Shape::Capsule { radius: 0.06, half_length: 0.14 }, 0.2),
+    ];
+    let mut tops = Vec::new();
+    for ((shape, half_height), anchor) in shapes.into_iter().zip(anchors.iter()) {
+        let body = RigidBody::new(*anchor - Vec3::Y * (length + half_height), shape, Vec3::ZERO, density);
+        tops.push((spawn_body(commands, meshes, materials, physics, body, color), half_height));
+    }
+    let capsule = tops[3].0;
 
-        add_distance_joint_new(&mut commands, &mut meshes, &mut materials, entity, prev_box, box_size.y, prev_size, length, _level, box_pos.y ,&mut query);
+    let tail_body = RigidBody::new(anchors[3] - Vec3::Y * (2.0 * length + 0.4 + 0.08), Shape::Sphere { radius: 0.08 }, Vec3::ZERO, density);
+    let tail = spawn_body(commands, meshes, materials, physics, tail_body, color);
 
-        prev_box = Some(entity);
-        prev_size = box_size.y;
-        box_size *= rng.gen_range(1.4..1.7); // 更新 boxSize
+    for ((top, half_height), anchor) in tops.iter().zip(anchors.iter()) {
+        let joint_body = DistanceConstraint::new(*top, None, vec3(0.0, *half_height, 0.0), *anchor,
+                                                 length, 0.0, DistanceMode::Rod);
+        add_joint(commands, meshes, materials, physics, joint_body, Some(*anchor));
     }
+
+    let joint_body = DistanceConstraint::new(tail, Some(capsule), vec3(0.0, 0.08, 0.0), vec3(0.0, -0.2, 0.0),
+                                             length, 0.0, DistanceMode::Rod);
+    add_joint(commands, meshes, materials, physics, joint_body, None);
 }
 
-fn scene_chain_timer(mut commands: &mut Commands,
-               mut meshes: &mut ResMut<Assets<Mesh>>,
-               mut materials: &mut ResMut<Assets<StandardMaterial>>,
-               mut query: &mut Query<(&mut CuboidBody, &mut Transform)>,
-                     time: &Res<Time>,
+// 随机点云生成的石块, 以及由 Bevy 网格生成的凸包, 一起落到地面上
+fn scene_hulls(commands: &mut Commands,
+               meshes: &mut ResMut<Assets<Mesh>>,
+               materials: &mut ResMut<Assets<StandardMaterial>>,
+               physics: &mut PhysicsWorld,
 )
 {
-    let mut prev_x = 0.0;
-
-    let mut box_size = vec3(0.1, 0.1, 0.1);
-    let mut box_pos = vec3(prev_x, 3.0, -1.0);
-    let dist = 0.2;
-    let mut prev_size = 0.0;
-    let mut prev_box = add_cuboid_body(&mut commands, &mut meshes, &mut materials, box_pos, box_size, true, time.elapsed_seconds());
+    let density = 20.0;
     let mut rng = rand::thread_rng();
-    for _level in 0..4 {
-        prev_x = box_pos.x;
-        box_pos.x += dist + box_size.x;
-        // if _level % 2 == 0 {
-        //     box_pos.x = 0.3;
-        // }
-        // else {
-        //     box_pos.x = -0.3;
-        // }
-        let entity = add_cuboid_body(&mut commands, &mut meshes, &mut materials, box_pos, box_size, false, 0.0);
-        let length = (box_pos.x - prev_x  - prev_size * 0.5 - box_size.x * 0.5).abs() * 1.2;
-        //println!("length: {} ", length);
-
-        add_distance_joint_timer(&mut commands, entity, prev_box, box_size.x, prev_size, length, &mut query);
-
-        prev_box = entity;
-        prev_size = box_size.x;
-        box_size *= rng.gen_range(0.8..1.5);
+    let mut hulls = Vec::new();
+    for _i in 0..10 {
+        let points: Vec<Vec3> = (0..16).map(|_| vec3(
+            rng.gen_range(-0.15..0.15),
+            rng.gen_range(-0.1..0.1),
+            rng.gen_range(-0.12..0.12),
+        )).collect();
+        hulls.extend(ConvexHull::from_points(&points));
     }
-}
+    if let Ok(ico_sphere) = Sphere::new(0.15).mesh().ico(1) {
+        hulls.extend(hull_from_mesh(&ico_sphere));
+    }
+    // 凸包的面数随网格分辨率增长, 用低分辨率的圆环控制窄相开销
+    let torus = Torus::new(0.06, 0.18).mesh().minor_resolution(8).major_resolution(12).build();
+    hulls.extend(hull_from_mesh(&torus));
 
-fn add_cuboid_body(commands: &mut Commands,
-                   meshes: &mut ResMut<Assets<Mesh>>,
-                   materials: &mut ResMut<Assets<StandardMaterial>>,
-                   init_pos: Vec3,
-                   init_size: Vec3,
-                   b_static: bool,
-                   spawn_time:f32,
+    for (i, hull) in hulls.into_iter().enumerate() {
+        let pos = vec3((i % 4) as f32 * 0.45 - 0.675, 0.4 + (i / 4) as f32 * 0.45, rng.gen_range(-0.1..0.1));
+        let angles = vec3(rng.gen_range(0.0..PI), rng.gen_range(0.0..PI), rng.gen_range(0.0..PI));
+        let body = RigidBody::new(pos, Shape::ConvexHull(hull), angles, density);
+        let color = Color::srgb(rng.gen_range(0.4..0.8), rng.gen_range(0.4..0.6), rng.gen_range(0.3..0.5));
+        spawn_body(commands, meshes, materials, physics, body, color);
+    }
+}
 
-) -> Entity
+// 由多个子形状组成的刚体: L 形支架、桌子和哑铃
+fn scene_compound(commands: &mut Commands,
+                  meshes: &mut ResMut<Assets<Mesh>>,
+                  materials: &mut ResMut<Assets<StandardMaterial>>,
+                  physics: &mut PhysicsWorld,
+)
 {
-    let init_angle = vec3(0.0, 0.0, 0.0);
+    let density = 20.0;
+    let color = Color::srgb_u8(124, 144, 255);
 
-    let  mut cuboid_body = CuboidBody::new(init_pos, init_size, init_angle, 20.0);
-    cuboid_body.set_sleep(b_static);
-    cuboid_body.sleep_time = spawn_time;
+    let bracket = Shape::Compound(vec![
+        CompoundChild::new(Shape::Cuboid { size: vec3(0.4, 0.06, 0.15) }, vec3(0.2, 0.0, 0.0), Quat::IDENTITY),
+        CompoundChild::new(Shape::Cuboid { size: vec3(0.06, 0.3, 0.15) }, vec3(0.0, 0.18, 0.0), Quat::IDENTITY),
+    ]);
 
-    let entity_idx = commands.spawn(PbrBundle {
-        mesh: meshes.add(Cuboid::new(init_size.x, init_size.y, init_size.z)),
-        material: materials.add(Color::srgb_u8(124, 144, 255)),
-        transform: Transform::from_xyz(init_pos.x, init_pos.y, init_pos.z),
-        ..default()
-    }).insert(cuboid_body).id();
-    entity_idx
+    let mut table_children = vec![
+        CompoundChild::new(Shape::Cuboid { size: vec3(0.6, 0.04, 0.4) }, vec3(0.0, 0.3, 0.0), Quat::IDENTITY),
+    ];
+    for (x, z) in [(-0.26, -0.16), (0.26, -0.16), (-0.26, 0.16), (0.26, 0.16)] {
+        table_children.push(CompoundChild::new(Shape::Cylinder { radius: 0.025, height: 0.28 }, vec3(x, 0.14, z), Quat::IDENTITY));
+    }
+    let table = Shape::Compound(table_children);
+
+    let dumbbell = Shape::Compound(vec![
+        CompoundChild::new(Shape::Capsule { radius: 0.03, half_length: 0.15 }, Vec3::ZERO, Quat::from_rotation_z(FRAC_PI_2)),
+        CompoundChild::new(Shape::Sphere { radius: 0.08 }, vec3(-0.2, 0.0, 0.0), Quat::IDENTITY),
+        CompoundChild::new(Shape::Sphere { radius: 0.08 }, vec3(0.2, 0.0, 0.0), Quat::IDENTITY),
+    ]);
+
+    let table_body = RigidBody::new(vec3(0.0, 0.2, 0.0), table, Vec3::ZERO, density);
+    spawn_body(commands, meshes, materials, physics, table_body, color);
+
+    for i in 0..3 {
+        let angles = vec3(0.3 * i as f32, 0.8 * i as f32, 0.2);
+        let bracket_body = RigidBody::new(vec3(-0.3 + 0.3 * i as f32, 1.0 + 0.4 * i as f32, 0.0), bracket.clone(), angles, density);
+        spawn_body(commands, meshes, materials, physics, bracket_body, Color::srgb(0.8, 0.5, 0.3));
+    }
+
+    let dumbbell_body = RigidBody::new(vec3(0.0, 0.3, 0.6), dumbbell, vec3(0.0, 0.5, 0.0), density);
+    spawn_body(commands, meshes, materials, physics, dumbbell_body, color);
 }
 
-fn add_distance_joint(commands: &mut Commands,
-                      meshes: &mut ResMut<Assets<Mesh>>,
-                      materials: &mut ResMut<Assets<StandardMaterial>>,
-                      body: Entity,
-                      body1: Option<Entity>,
+// 失重中的两个 T 形把手: 绕中间主轴旋转的会周期性翻转(贾尼别科夫效应), 绕最大主轴旋转的保持稳定
+fn scene_t_handle(commands: &mut Commands,
+                  meshes: &mut ResMut<Assets<Mesh>>,
+                  materials: &mut ResMut<Assets<StandardMaterial>>,
+                  physics: &mut PhysicsWorld,
+)
+{
+    let density = 20.0;
+    let t_handle = Shape::Compound(vec![
+        CompoundChild::new(Shape::Cylinder { radius: 0.03, height: 0.3 }, Vec3::ZERO, Quat::IDENTITY),
+        CompoundChild::new(Shape::Cylinder { radius: 0.03, height: 0.4 }, vec3(0.0, 0.18, 0.0), Quat::from_rotation_z(FRAC_PI_2)),
+    ]);
 
-                      query: &mut Query<(&mut CuboidBody, &mut Transform)>,
+    // 惯量张量对角, x 为中间主轴, z 为最大主轴; 加一点扰动让不稳定性显现
+    let spins = [
+        (vec3(-0.5, 1.8, 0.0), vec3(8.0, 0.02, 0.02), Color::srgb(0.8, 0.5, 0.3)),
+        (vec3(0.5, 1.8, 0.0), vec3(0.02, 0.02, 8.0), Color::srgb_u8(124, 144, 255)),
+    ];
+    for (pos, angular_velocity, color) in spins {
+        let mut body = RigidBody::new(pos, t_handle.clone(), Vec3::ZERO, density);
+        body.set_angular_velocity(angular_velocity);
+        spawn_body(commands, meshes, materials, physics, body, color);
+    }
+}
 
-                      init_pos: Vec3,
-                      loc_pos: Vec3,
+// 关节示例: 带限位的门、固定转轴的摆、一串胶囊组成的连杆, 后排用球铰串起来的方块链和焊接件, 前排的滑块, 最前排由电机驱动,
+// 最后排是可以拉断的绳子和搁板, 以及弹簧
+fn scene_joints(commands: &mut Commands,
+                meshes: &mut ResMut<Assets<Mesh>>,
+                materials: &mut ResMut<Assets<StandardMaterial>>,
+                physics: &mut PhysicsWorld,
 )
 {
-    let y_len = 0.6;
-    let mut target_pos = init_pos;
-    target_pos.y += y_len;
-    let joint_body = DistanceConstraint::new(body,
-                                                 body1,
-                                                 loc_pos,
-                                                 target_pos,
-                                                 y_len,
-                                                 0.001,
-                                                 true, query);
+    let density = 20.0;
+    let color = Color::srgb_u8(124, 144, 255);
 
+    // 门的左边缘沿竖直方向铰接在世界上, 只能在 ±90° 之间转动
+    let door_body = RigidBody::new(vec3(-1.2, 1.0, 0.0), Shape::Cuboid { size: vec3(0.6, 1.0, 0.05) }, Vec3::ZERO, density);
+    let door = spawn_body(commands, meshes, materials, physics, door_body, Color::srgb(0.8, 0.5, 0.3));
+    let mut door_hinge = HingeJoint::new(physics, door, None, vec3(-1.5, 1.0, 0.0), Vec3::Y, 0.0);
+    door_hinge.set_limits(-FRAC_PI_2, FRAC_PI_2);
+    add_joint(commands, meshes, materials, physics, door_hinge, Some(vec3(-1.5, 1.0, 0.0)));
 
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(Circle::new(0.02)),
-        material: materials.add(Color::srgb_u8(0, 255, 0)),
-        transform: Transform::from_xyz(target_pos.x, target_pos.y, target_pos.z),
-        ..default()
-    }).insert(joint_body);
-}
+    // 摆杆绕 z 轴摆动, 从水平位置释放
+    let pendulum_body = RigidBody::new(vec3(-0.1, 2.2, 0.0), Shape::Cuboid { size: vec3(0.6, 0.06, 0.06) }, Vec3::ZERO, density);
+    let pendulum = spawn_body(commands, meshes, materials, physics, pendulum_body, color);
+    let joint = HingeJoint::new(physics, pendulum, None, vec3(-0.4, 2.2, 0.0), Vec3::Z, 0.0);
+    add_joint(commands, meshes, materials, physics, joint, Some(vec3(-0.4, 2.2, 0.0)));
+
+    // 连杆之间留出间隙, 相邻两节最多相对转动 45°
+    let link_length = 0.3;
+    let gap = 0.04;
+    let mut prev: Option<BodyHandle> = None;
+    let mut anchor = vec3(0.4, 2.2, 0.0);
+    for _i in 0..4 {
+        let center = anchor + Vec3::X * (0.5 * gap + 0.5 * link_length);
+        let link_body = RigidBody::new(center, Shape::Capsule { radius: 0.04, half_length: 0.5 * link_length - 0.04 },
+                                       vec3(0.0, 0.0, FRAC_PI_2), density);
+        let link = spawn_body(commands, meshes, materials, physics, link_body, color);
+        match prev {
+            Some(prev_link) => {
+                let mut hinge = HingeJoint::new(physics, prev_link, Some(link), anchor, Vec3::Z, 0.0);
+                hinge.set_limits(-0.25 * PI, 0.25 * PI);
+                add_joint(commands, meshes, materials, physics, hinge, None);
+            }
+            None => {
+                let joint = HingeJoint::new(physics, link, None, anchor, Vec3::Z, 0.0);
+                add_joint(commands, meshes, materials, physics, joint, Some(anchor));
+            }
+        }
+        prev = Some(link);
+        anchor = center + Vec3::X * (0.5 * link_length + 0.5 * gap);
+    }
+
+    // 方块之间的间隙保证摆到锥角限制时也不会互相碰到
+    let box_size = vec3(0.2, 0.08, 0.08);
+    let gap = 0.08;
+    let mut prev: Option<BodyHandle> = None;
+    let mut anchor = vec3(-0.9, 2.6, -0.8);
+    for _i in 0..5 {
+        let center = anchor + Vec3::X * (0.5 * gap + 0.5 * box_size.x);
+        let link_body = RigidBody::new(center, Shape::Cuboid { size: box_size }, Vec3::ZERO, density);
+        let link = spawn_body(commands, meshes, materials, physics, link_body, Color::srgb(0.3, 0.7, 0.5));
+        match prev {
+            Some(prev_link) => {
+                let mut joint = SphericalJoint::new(physics, prev_link, Some(link), anchor, Vec3::X, 0.0);
+                joint.set_swing_limit(PI / 6.0);
+                joint.set_twist_limits(-PI / 18.0, PI / 18.0);
+                add_joint(commands, meshes, materials, physics, joint, None);
+            }
+            None => {
+                let joint = SphericalJoint::new(physics, link, None, anchor, Vec3::X, 0.0);
+                add_joint(commands, meshes, materials, physics, joint, Some(anchor));
+            }
+        }
+        prev = Some(link);
+        anchor = center + Vec3::X * (0.5 * box_size.x + 0.5 * gap);
+    }
+
+    // 摆臂上套一个滑块, 摆动时滑块沿臂滑到限位处; 滑块在 z 方向错开, 不与摆臂接触
+    let arm_body = RigidBody::new(vec3(-0.2, 1.6, 0.8), Shape::Cuboid { size: vec3(0.8, 0.06, 0.06) }, Vec3::ZERO, density);
+    let arm = spawn_body(commands, meshes, materials, physics, arm_body, color);
+    let joint = HingeJoint::new(physics, arm, None, vec3(-0.6, 1.6, 0.8), Vec3::Z, 0.0);
+    add_joint(commands, meshes, materials, physics, joint, Some(vec3(-0.6, 1.6, 0.8)));
+    let slider_body = RigidBody::new(vec3(-0.4, 1.6, 0.88), Shape::Cuboid { size: vec3(0.1, 0.1, 0.08) }, Vec3::ZERO, density);
+    let slider = spawn_body(commands, meshes, materials, physics, slider_body, Color::srgb(0.8, 0.5, 0.3));
+    let mut slider_joint = PrismaticJoint::new(physics, arm, Some(slider), vec3(-0.4, 1.6, 0.88), Vec3::X, 0.0);
+    slider_joint.set_limits(-0.15, 0.35);
+    add_joint(commands, meshes, materials, physics, slider_joint, None);
+
+    // 沿 30° 斜向导轨滑下的方块, 滑过 0.5 后停住
+    let rail_dir = vec3(30.0_f32.to_radians().cos(), -30.0_f32.to_radians().sin(), 0.0);
+    let rail_body = RigidBody::new(vec3(0.9, 1.2, 0.8), Shape::Cuboid { size: vec3(0.15, 0.1, 0.1) }, vec3(0.0, 0.0, -PI / 6.0), density);
+    let rail_block = spawn_body(commands, meshes, materials, physics, rail_body, Color::srgb(0.8, 0.5, 0.3));
+    let mut rail_joint = PrismaticJoint::new(physics, rail_block, None, vec3(0.9, 1.2, 0.8), rail_dir, 0.0);
+    rail_joint.set_limits(-0.5, 0.0);
+    add_joint(commands, meshes, materials, physics, rail_joint, None);
+
+    // 立柱焊在世界上, 横臂焊在立柱顶端, 两者之间留出间隙
+    let post_body = RigidBody::new(vec3(1.0, 0.25, -0.8), Shape::Cuboid { size: vec3(0.06, 0.4, 0.06) }, Vec3::ZERO, density);
+    let post = spawn_body(commands, meshes, materials, physics, post_body, color);
+    let joint = FixedJoint::new(physics, post, None, vec3(1.0, 0.05, -0.8), 0.0, 0.0);
+    add_joint(commands, meshes, materials, physics, joint, Some(vec3(1.0, 0.05, -0.8)));
+    let beam_body = RigidBody::new(vec3(1.25, 0.42, -0.8), Shape::Cuboid { size: vec3(0.4, 0.06, 0.06) }, Vec3::ZERO, density);
+    let beam = spawn_body(commands, meshes, materials, physics, beam_body, color);
+    let joint = FixedJoint::new(physics, post, Some(beam), vec3(1.04, 0.42, -0.8), 0.0, 0.0);
+    add_joint(commands, meshes, materials, physics, joint, None);
+
+    // 软焊的跳板, 方块落上去后跳板上下弹动; 方块从上风处落下, 免得被风吹离跳板
+    let board_body = RigidBody::new(vec3(1.3, 1.0, -0.8), Shape::Cuboid { size: vec3(0.6, 0.03, 0.4) }, Vec3::ZERO, density);
+    let board = spawn_body(commands, meshes, materials, physics, board_body, Color::srgb(0.8, 0.5, 0.3));
+    let joint = FixedJoint::new(physics, board, None, vec3(1.0, 1.0, -0.8), 0.0, 0.1);
+    add_joint(commands, meshes, materials, physics, joint, Some(vec3(1.0, 1.0, -0.8)));
+    let drop_body = RigidBody::new(vec3(1.5, 1.3, -0.65), Shape::Cuboid { size: vec3(0.1, 0.1, 0.1) }, Vec3::ZERO, density);
+    spawn_body(commands, meshes, materials, physics, drop_body, color);
+
+    // 传送带: 一排滚筒由速度电机带动, 把上面的箱子往 +x 方向送
+    let roller_radius = 0.06;
+    for i in 0..5 {
+        let center = vec3(-1.2 + 0.14 * i as f32, 0.3, 1.6);
+        let roller_body = RigidBody::new(center, Shape::Cylinder { radius: roller_radius, height: 0.4 }, vec3(FRAC_PI_2, 0.0, 0.0), density);
+        let roller = spawn_body(commands, meshes, materials, physics, roller_body, color);
+        let mut roller_joint = HingeJoint::new(physics, roller, None, center, Vec3::Z, 0.0);
+        // 滚筒顺时针转动时顶面向 +x 运动
+        roller_joint.set_motor(JointMotor::Velocity { target: 2.0, max_force: 5.0 });
+        add_joint(commands, meshes, materials, physics, roller_joint, Some(center));
+    }
+    // 箱子做得扁长, 总是搭在两个滚筒上, 不会卡进滚筒之间的缝里
+    let cargo_body = RigidBody::new(vec3(-1.06, 0.3 + roller_radius + 0.03, 1.6), Shape::Cuboid { size: vec3(0.3, 0.06, 0.2) }, Vec3::ZERO, density);
+    spawn_body(commands, meshes, materials, physics, cargo_body, Color::srgb(0.8, 0.5, 0.3));
+
+    // 两节机械臂, 每个关节用位置电机驱动到目标角度, 目标值单位为弧度
+    let link_length = 0.3;
+    let gap = 0.04;
+    let mut prev: Option<BodyHandle> = None;
+    let mut anchor = vec3(-0.2, 1.0, 1.6);
+    for _i in 0..2 {
+        let center = anchor + Vec3::X * (0.5 * gap + 0.5 * link_length);
+        let link_body = RigidBody::new(center, Shape::Capsule { radius: 0.04, half_length: 0.5 * link_length - 0.04 },
+                                       vec3(0.0, 0.0, FRAC_PI_2), density);
+        let link = spawn_body(commands, meshes, materials, physics, link_body, Color::srgb(0.3, 0.7, 0.5));
+        let motor = JointMotor::Position { target: 0.0, compliance: 0.0, max_force: 20.0 };
+        match prev {
+            Some(prev_link) => {
+                let mut hinge = HingeJoint::new(physics, prev_link, Some(link), anchor, Vec3::Z, 0.0);
+                hinge.set_motor(motor);
+                add_joint(commands, meshes, materials, physics, hinge, None);
+            }
+            None => {
+                let mut hinge = HingeJoint::new(physics, link, None, anchor, Vec3::Z, 0.0);
+                hinge.set_motor(motor);
+                add_joint(commands, meshes, materials, physics, hinge, Some(anchor));
+            }
+        }
+        prev = Some(link);
+        anchor = center + Vec3::X * (0.5 * link_length + 0.5 * gap);
+    }
+
+    // 活塞: 平台沿竖直导轨被位置电机顶到目标高度, 目标值单位为米, 与机械臂的角度分开调节
+    let piston_anchor = vec3(0.9, 0.3, 1.6);
+    let piston_body = RigidBody::new(piston_anchor, Shape::Cuboid { size: vec3(0.3, 0.05, 0.3) }, Vec3::ZERO, density);
+    let piston = spawn_body(commands, meshes, materials, physics, piston_body, color);
+    // 滑动轴朝下, 锚点沿轴的距离就是平台升起的高度
+    let mut piston_joint = PrismaticJoint::new(physics, piston, None, piston_anchor, -Vec3::Y, 0.0);
+    piston_joint.set_limits(0.0, 0.5);
+    piston_joint.set_motor(JointMotor::Position { target: 0.0, compliance: 0.0, max_force: 5.0 });
+    add_joint(commands, meshes, materials, physics, piston_joint, Some(piston_anchor));
+
+    // 吊灯挂在绳子上, 加大密度后绳子被拉断
+    let box_size = 0.15;
+    let rope_anchor = vec3(-0.6, 2.2, -1.6);
+    let rope_length = 0.5;
+    let lamp_body = RigidBody::new(rope_anchor - Vec3::Y * (rope_length + 0.5 * box_size), Shape::Cuboid { size: Vec3::splat(box_size) }, Vec3::ZERO, density);
+    let lamp = spawn_body(commands, meshes, materials, physics, lamp_body, Color::srgb(0.8, 0.5, 0.3));
+    let mut rope = DistanceConstraint::new(lamp, None, vec3(0.0, 0.5 * box_size, 0.0), rope_anchor, rope_length,
+                                           ROPE_COMPLIANCE, DistanceMode::Rope);
+    rope.set_break_force(1.5);
+    // 静止时张力应为 m * |g + wind|
+    let rope_entity = add_joint(commands, meshes, materials, physics, rope, Some(rope_anchor));
+    commands.entity(rope_entity).insert(SensorDisplay);
 
-fn add_distance_joint_new(commands: &mut Commands,
-                                    meshes: &mut ResMut<Assets<Mesh>>,
-                                    materials: &mut ResMut<Assets<StandardMaterial>>,
-                                    body: Entity,
-                                    body1: Option<Entity>,
-                                    box_size: f32,
-                                    box1_size: f32,
-                                    dis: f32,
-                                    idx: i32,
-                                    y_pos: f32,
-                                    query: &mut Query<(&mut CuboidBody, &mut Transform)>,
+    // 搁板的一边焊在世界上, 上面放一个箱子, 加大密度后焊点断开, 搁板和箱子一起掉下来
+    let shelf_anchor = vec3(0.1, 1.2, -1.6);
+    let shelf_body = RigidBody::new(shelf_anchor + Vec3::X * 0.2, Shape::Cuboid { size: vec3(0.4, 0.03, 0.3) }, Vec3::ZERO, density);
+    let shelf = spawn_body(commands, meshes, materials, physics, shelf_body, color);
+    let mut shelf_joint = FixedJoint::new(physics, shelf, None, shelf_anchor, 0.0, 0.0);
+    shelf_joint.set_break_force(6.0);
+    add_joint(commands, meshes, materials, physics, shelf_joint, Some(shelf_anchor));
+    let load_body = RigidBody::new(shelf_anchor + vec3(0.25, 0.015 + 0.5 * box_size, 0.0), Shape::Cuboid { size: Vec3::splat(box_size) }, Vec3::ZERO, density);
+    spawn_body(commands, meshes, materials, physics, load_body, Color::srgb(0.8, 0.5, 0.3));
 
+    // 两个挂在弹簧上的方块, 右边的带阻尼, 上下振动很快停下来; 左边没有阻尼, 每次拉长都撞到限位
+    let spring_length = 0.4;
+    for (x, damping) in [(1.0, 0.0), (1.4, 0.3)] {
+        let spring_anchor = vec3(x, 2.2, -1.6);
+        let weight_body = RigidBody::new(spring_anchor - Vec3::Y * (spring_length + 0.5 * box_size), Shape::Cuboid { size: Vec3::splat(box_size) }, Vec3::ZERO, density);
+        let weight = spawn_body(commands, meshes, materials, physics, weight_body, Color::srgb(0.3, 0.7, 0.5));
+        let spring = DistanceConstraint::new(weight, None, vec3(0.0, 0.5 * box_size, 0.0), spring_anchor, spring_length,
+                                             0.0, DistanceMode::Spring { stiffness: 5.0, damping });
+        add_joint(commands, meshes, materials, physics, spring, Some(spring_anchor));
+        // 限位: 弹簧拉长到 0.6 时被挡住
+        let stop = DistanceConstraint::new(weight, None, vec3(0.0, 0.5 * box_size, 0.0), spring_anchor, spring_length,
+                                           0.0, DistanceMode::Range { min: 0.35, max: 0.6 });
+        add_joint(commands, meshes, materials, physics, stop, None);
+    }
+}
+
+// 载入场景文件: 读取成功后才清空当前场景, 地面随场景替换; 重力, 风和求解参数写到 PhysicsConfig, 刚体和关节加到物理世界, 再生成显示用的实体
+fn load_scene(commands: &mut Commands,
+              meshes: &mut ResMut<Assets<Mesh>>,
+              materials: &mut ResMut<Assets<StandardMaterial>>,
+              query: &mut Query<Entity, (Without<Text>, Without<Window>)>,
+              physics: &mut PhysicsWorld,
+              config: &mut PhysicsConfig,
+              path: &Path,
 )
 {
-    let mut prev_pos = vec3(0.0, -0.5 * box1_size, 0.0);
-    if idx == 0{
-        prev_pos = vec3(0.0, y_pos + dis, 0.0);
-    }
-    let joint_body = DistanceConstraint::new(body,
-                                                 body1,
-                                                 vec3(0.4 * box_size ,0.5 * box_size, 0.0),
-                                                 prev_pos,
-                                                 dis,
-                                                 0.001,
-                                                 true, query);
-
-    if body1 == None{
-        commands.spawn(PbrBundle {
-            mesh: meshes.add(Circle::new(0.02)),
-            material: materials.add(Color::srgb_u8(0, 255, 0)),
-            transform: Transform::from_xyz(prev_pos.x, prev_pos.y, prev_pos.z),
-            ..default()
-        }).insert(joint_body);
+    let scene = match SceneFile::load(path) {
+        Ok(scene) => scene,
+        Err(err) => {
+            error!("failed to load scene {}: {err}", path.display());
+            return;
+        }
+    };
+    physics.ground = scene.ground;
+    clear_scene(commands, meshes, materials, query, physics);
+    scene.apply_config(config);
+    let (bodies, joints) = match scene.build(physics) {
+        Ok(handles) => handles,
+        Err(err) => {
+            error!("failed to build scene {}: {err}", path.display());
+            return;
+        }
+    };
+    for (desc, handle) in scene.bodies.iter().zip(bodies) {
+        spawn_body_mesh(commands, meshes, materials, physics, handle, body_color(desc));
     }
-    else {
-        commands.spawn(PbrBundle {
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
-            ..default()
-        }).insert(joint_body);
+    for (desc, handle) in scene.joints.iter().zip(joints) {
+        spawn_joint_marker(commands, meshes, materials, JointRef(handle), desc.world_anchor());
     }
 }
-fn add_distance_joint_timer(commands: &mut Commands,
-                            body: Entity,
-                            body1:Entity,
-                            box_size: f32,
-                            prev_size: f32,
-                            dis: f32,
-                            query: &mut Query<(&mut CuboidBody, &mut Transform)>,
 
-)
+// 关节加入物理世界, 另外生成一个实体指向它, 连接到世界的关节在固定点处画一个标记
+// 返回这个实体, 可以再给它加上 SensorDisplay 等附加组件
+fn add_joint(commands: &mut Commands,
+             meshes: &mut ResMut<Assets<Mesh>>,
+             materials: &mut ResMut<Assets<StandardMaterial>>,
+             physics: &mut PhysicsWorld,
+             joint: impl Into<Joint>,
+             marker: Option<Vec3>,
+) -> Entity
+{
+    let joint_ref = JointRef(physics.add_joint(joint));
+    spawn_joint_marker(commands, meshes, materials, joint_ref, marker)
+}
+
+// 每个关节实体都带 SensorReadout
+fn spawn_joint_marker(commands: &mut Commands,
+                      meshes: &mut ResMut<Assets<Mesh>>,
+                      materials: &mut ResMut<Assets<StandardMaterial>>,
+                      joint_ref: JointRef,
+                      marker: Option<Vec3>,
+) -> Entity
 {
-    let prev_pos = vec3(0.5 * prev_size, 0.2 * prev_size, 0.0);
-    let joint_body = DistanceConstraint::new(body,
-                                             Some(body1),
-                                             vec3(-0.5 * box_size ,0.2 * box_size, 0.0),
-                                             prev_pos,
-                                             dis,
-                                             0.001,
-                                             true, query);
+    match marker {
+        Some(pos) => {
+            commands.spawn(PbrBundle {
+                mesh: meshes.add(Circle::new(0.02)),
+                material: materials.add(Color::srgb_u8(0, 255, 0)),
+                transform: Transform::from_translation(pos),
+                ..default()
+            }).insert((joint_ref, SensorReadout::default())).id()
+        }
+        None => {
+            commands.spawn(PbrBundle {
+                transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                ..default()
+            }).insert((joint_ref, SensorReadout::default())).id()
+        }
+    }
+}
+
+// 按描述生成刚体, 以物理世界的当前时刻为创建时刻; 描述无效时不生成
+fn add_body(commands: &mut Commands,
+            meshes: &mut ResMut<Assets<Mesh>>,
+            materials: &mut ResMut<Assets<StandardMaterial>>,
+            physics: &mut PhysicsWorld,
+            desc: &BodyDesc,
+) -> Option<BodyHandle>
+{
+    match desc.to_body(physics.time()) {
+        Ok(body) => Some(spawn_body(commands, meshes, materials, physics, body, body_color(desc))),
+        Err(reason) => {
+            error!("invalid body: {reason}");
+            None
+        }
+    }
+}
+
+// 场景文件里省略颜色时用默认的蓝色
+fn body_color(desc: &BodyDesc) -> Color {
+    match desc.color {
+        Some((r, g, b)) => Color::srgb(r, g, b),
+        None => Color::srgb_u8(124, 144, 255),
+    }
+}
 
+fn spawn_body(commands: &mut Commands,
+              meshes: &mut ResMut<Assets<Mesh>>,
+              materials: &mut ResMut<Assets<StandardMaterial>>,
+              physics: &mut PhysicsWorld,
+              body: RigidBody,
+              color: Color,
+) -> BodyHandle
+{
+    let handle = physics.add_body(body);
+    spawn_body_mesh(commands, meshes, materials, physics, handle, color);
+    handle
+}
 
+// 为物理世界里已有的刚体生成显示用的实体
+fn spawn_body_mesh(commands: &mut Commands,
+                   meshes: &mut ResMut<Assets<Mesh>>,
+                   materials: &mut ResMut<Assets<StandardMaterial>>,
+                   physics: &mut PhysicsWorld,
+                   handle: BodyHandle,
+                   color: Color,
+)
+{
+    let Some(body) = physics.body_mut(handle) else {
+        return;
+    };
+    // 网格定义在形状坐标系下, 平移到以质心为原点
+    let mesh = shape_mesh(body.get_shape()).translated_by(-body.get_centroid());
+    let transform = Transform::from_translation(body.get_position())
+        .with_rotation(body.get_quat());
     commands.spawn(PbrBundle {
-        transform: Transform::from_xyz(0.0, 0.0, 0.0),
+        mesh: meshes.add(mesh),
+        material: materials.add(color),
+        transform,
         ..default()
-    }).insert(joint_body);
+    }).insert(BodyRef(handle));
 }
 
-
 fn tick(
-    time: Res<Time>,
     setting: ResMut<Setting>,
-    mut query: Query<(Entity, &mut CuboidBody, &mut Transform)>,
-    mut joint_query: Query<&mut DistanceConstraint>,
+    mut physics: ResMut<Physics>,
+    mut commands: Commands,
+    joint_query: Query<(Entity, &JointRef)>,
+    mut event_writer: EventWriter<JointBrokenEvent>,
 ) {
     if setting.pause{return;}
-    let delta_time = time.delta_seconds();
-    let num_sub_steps = 8;
-    let mut s_dt = delta_time / num_sub_steps as f32;
-    s_dt /= 1.0;
-    let g = vec3(0.0, -9.80, 0.0) + setting.wind;
-    for _i in 0..num_sub_steps {
-
-        for (_entity, mut cuboid_body,  _transform)in query.iter_mut() {
-            cuboid_body.step(s_dt, g);
-        }
-
-        for mut distance_joint in joint_query.iter_mut() {
-            distance_joint.solve(&mut query);
+    let broken_joints = physics.step();
+    if broken_joints.is_empty() {
+        return;
+    }
+    // 删除已经拉断的关节对应的实体并发出 JointBrokenEvent
+    for (entity, joint_ref) in joint_query.iter() {
+        if let Some(broken) = broken_joints.iter().find(|broken| broken.joint == joint_ref.0) {
+            commands.entity(entity).despawn();
+            event_writer.send(JointBrokenEvent { entity, broken: *broken });
         }
-
-        for (_entity, mut cuboid_body,  _transform,)in query.iter_mut() {
-            cuboid_body.update_vel(s_dt);
+    }
+}
+// 把关节的长度, 张力和伸长速度写到传感器里
+fn update_joint_sensors(
+    physics: Res<Physics>,
+    mut query: Query<(&JointRef, &mut SensorReadout)>,
+)
+{
+    for (joint_ref, mut sensor) in query.iter_mut() {
+        if let Some(joint) = physics.joint(joint_ref.0) {
+            sensor.0 = joint.get_sensor();
         }
     }
-
-
-
+}
+// 统计当前场景里断开的关节
+fn count_broken_joints(
+    mut setting: ResMut<Setting>,
+    mut event_reader: EventReader<JointBrokenEvent>,
+)
+{
+    setting.broken_joints += event_reader.read().count();
 }
 fn pre_tick(
     setting: ResMut<Setting>,
-    mut query: Query<(&mut CuboidBody)>,
+    mut physics: ResMut<Physics>,
 )
 {
-    for (mut cuboid_body)in query.iter_mut() {
-        cuboid_body.set_mass(setting.mass_factor);
+    for (_handle, body) in physics.bodies_mut() {
+        body.set_mass(setting.mass_factor);
     }
 }
+// 按键调节目标速度和目标值时才交给场景中的电机, 其余时候保留各电机自己的目标
+fn drive_motors(setting: &Setting, physics: &mut PhysicsWorld) {
+    for (_handle, joint) in physics.joints_mut() {
+        match joint {
+            Joint::Hinge(hinge_joint) => {
+                if let Some(motor) = hinge_joint.get_motor() {
+                    hinge_joint.set_motor(motor.with_targets(setting.motor_speed, setting.motor_target));
+                }
+            }
+            Joint::Prismatic(prismatic_joint) => {
+                if let Some(motor) = prismatic_joint.get_motor() {
+                    prismatic_joint.set_motor(motor.with_targets(setting.slide_speed, setting.slide_target));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+#[allow(clippy::type_complexity)]
 fn show(
-    time: Res<Time>,
-    mut gizmos: Gizmos,
-    mut query: Query<(Entity, &mut CuboidBody, &mut Transform, &mut Handle<StandardMaterial>)>,
-    mut joint_query: Query<&mut DistanceConstraint>,
+    mut commands: Commands,
+    physics: Res<Physics>,
+    mut query: Query<(Entity, &BodyRef, &mut Transform, &mut Handle<StandardMaterial>, Has<Asleep>)>,
     mut materials:  ResMut<Assets<StandardMaterial>>,
 ) {
-    for (_entity, mut cuboid_body,  mut transform , mut _m)in query.iter_mut() {
-        if(cuboid_body.ready_sleep(time.elapsed_seconds()))
-        {
-            *_m = materials.add(Color::srgb(0.0, 1.0, 0.0));
+    for (entity, body_ref, mut transform, mut material, asleep) in query.iter_mut() {
+        let Some(body) = physics.body(body_ref.0) else {
+            continue;
+        };
+        // 休眠在 PhysicsWorld::step 里按模拟时间判断, 这里只在刚睡着时换一次颜色
+        if !asleep && body.is_sleep() && body.is_static() {
+            *material = materials.add(Color::srgb(0.0, 1.0, 0.0));
+            commands.entity(entity).insert(Asleep);
         }
-        transform.translation = cuboid_body.get_position();
-        transform.rotation  = cuboid_body.get_quat();
+        transform.translation = body.get_position();
+        transform.rotation  = body.get_quat();
     }
-
-    for mut distance_joint in joint_query.iter_mut() {
-        distance_joint.show_line(&mut gizmos);
+}
+fn show_joints(
+    mut gizmos: Gizmos,
+    physics: Res<Physics>,
+) {
+    for (_handle, joint) in physics.joints() {
+        show_joint(&mut gizmos, joint);
     }
-
-}
\ No newline at end of file
+}
+// 关节的连线画成红色; 铰链的转轴, 滑动副的行程和球铰的锚点画成蓝色
+fn show_joint(gizmos: &mut Gizmos, joint: &Joint) {
+    let (world_pos0, world_pos1) = match joint {
+        Joint::Distance(distance_joint) => distance_joint.get_world_positions(),
+        Joint::Hinge(hinge_joint) => {
+            let (world_pos0, world_pos1) = hinge_joint.get_world_positions();
+            let axis = hinge_joint.get_world_axis();
+            gizmos.line(world_pos0 - axis * 0.15, world_pos0 + axis * 0.15, Color::srgb_u8(0, 0, 255));
+            (world_pos0, world_pos1)
+        }
+        Joint::Spherical(spherical_joint) => {
+            let (world_pos0, world_pos1) = spherical_joint.get_world_positions();
+            gizmos.sphere(world_pos0, Quat::IDENTITY, 0.02, Color::srgb_u8(0, 0, 255));
+            (world_pos0, world_pos1)
+        }
+        Joint::Prismatic(prismatic_joint) => {
+            let (world_pos0, world_pos1) = prismatic_joint.get_world_positions();
+            let axis = prismatic_joint.get_world_axis();
+            // body0 的锚点可以到达的范围
+            let (min, max) = prismatic_joint.get_limits().unwrap_or((-0.3, 0.3));
+            gizmos.line(world_pos1 - axis * max, world_pos1 - axis * min, Color::srgb_u8(0, 0, 255));
+            (world_pos0, world_pos1)
+        }
+        Joint::Fixed(fixed_joint) => fixed_joint.get_world_positions(),
+    };
+    gizmos.line(world_pos0, world_pos1, Color::srgb_u8(255, 0, 0));
+}